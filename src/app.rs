@@ -1,18 +1,40 @@
 use egui::*;
 
 use crate::agent::{AgentConfig, AgentRole, AgentSystem};
+use crate::binary::{self, CopyFormat, HexEditor, SaveFuture};
+use crate::bitview;
+use crate::bookmarks::{self, Bookmark, BookmarkAction, BookmarkMap, BookmarksPanel};
+use crate::hexview::{self, HexLayout};
 use crate::collab::{CollabSystem, CollabMessage};
+use crate::compare::Compare;
+use crate::export::{self, Export, ExportAction};
+use crate::codegen::{self, StructGen, StructGenAction};
+use crate::completion::{Candidate, Completion};
+use crate::decompress::{self, Codec};
+use crate::disasm::{self, DisasmAction, Disassembly};
+use crate::formats::{FormatAction, FormatsPanel, Library};
+use crate::hash::{self, HashPanel};
 use crate::hotkeys;
+use crate::inputs::{self, Input, Parent};
+use crate::inspector::{self, Inspector};
+use crate::kaitai;
 use crate::palette::{Palette, CommandAction};
+use crate::reads::{self, VarRange};
+use crate::search::Search;
 use crate::style::Theme;
-use crate::workspace::Workspace;
+use crate::tree::{self, StructureTree};
+use crate::workspace::{unique_buffer_name, Buffer, Workspace};
 use crate::file_explorer::FileExplorer;
 use uuid::Uuid;
 use crate::{canvas::*, layouter};
+use crate::layouter::Lexicon;
+use crate::overview::Overview;
+use crate::output;
 use std::fmt::Write;
+use std::ops::Range;
 use xeh::prelude::*;
 use xeh::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use similar::{ChangeTag, TextDiff};
 
 #[cfg(target_arch = "wasm32")]
@@ -20,7 +42,15 @@ type Instant = instant::Instant;
 #[cfg(not(target_arch = "wasm32"))]
 type Instant = std::time::Instant;
 
-type BoxFuture = Box<dyn Future<Output = Option<Vec<u8>>>>;
+type BoxFuture = Box<dyn Future<Output = Option<PickedFile>>>;
+
+/// File chosen in the open dialog, native builds load it by path so it can be mapped.
+enum PickedFile {
+    #[cfg(target_arch = "wasm32")]
+    Data(String, Vec<u8>),
+    #[cfg(not(target_arch = "wasm32"))]
+    Path(std::path::PathBuf),
+}
 
 #[derive(PartialEq)]
 enum HelpMode {
@@ -43,7 +73,12 @@ pub struct TemplateApp {
     view_pos: usize,
     num_rows: usize,
     num_cols: usize,
+    hex_layout: HexLayout,
     live_code: String,
+    // Editor tabs, `live_code` holds the text of the active one
+    buffers: Vec<Buffer>,
+    active_buffer: usize,
+    buffer_rename: String,
     trial_code: Option<Xstr>,
     frozen_code: Vec<FrozenStr>,
     last_dt: Option<(f64, &'static str)>,
@@ -55,9 +90,26 @@ pub struct TemplateApp {
     interval_timer: Instant,
     interval_word: String,
     insn_limit: Option<usize>,
-    snapshot: Option<(Xstate, Vec<FrozenStr>)>,
+    snapshot: Option<(Xstate, Vec<FrozenStr>, Vec<reads::Read>)>,
     bin_future: Option<Pin<BoxFuture>>,
-    input_binary: Option<Xbitstr>,
+    compare_future: Option<Pin<BoxFuture>>,
+    ksy_future: Option<Pin<BoxFuture>>,
+    save_future: Option<SaveFuture>,
+    // Files queued by output/save and the save buttons, saved one dialog at a time
+    pending_saves: Vec<output::SaveRequest>,
+    // Loaded binaries shown as tabs, the active one is the input scripts start with
+    inputs: Vec<Input>,
+    active_input: usize,
+    bookmarks: BookmarkMap,
+    bookmarks_panel: BookmarksPanel,
+    overview: Overview,
+    compare: Compare,
+    hex_edit: HexEditor,
+    // Bit ranges of the input consumed by reads, and the state they were computed for
+    read_ranges: Vec<VarRange>,
+    read_ranges_key: (u64, usize, reads::InputId),
+    // Wrap `read` to log the ranges, off the scripts run on the builtin
+    track_reads: bool,
     focus_on_code: bool,
     bytecode_open: bool,
     bytecode_follow: bool,
@@ -66,10 +118,25 @@ pub struct TemplateApp {
     goto_open: bool,
     goto_text: String,
     goto_old_pos: Option<usize>,
+    search: Search,
+    inspector: Inspector,
+    structure: StructureTree,
+    export: Export,
+    struct_gen: StructGen,
+    hash_panel: HashPanel,
+    disasm: Disassembly,
+    frozen_highlight: Option<usize>,
+    frozen_scroll: bool,
+    live_cursor_jump: Option<usize>,
     help: Help,
+    completion: Completion,
+    lexicon: Lexicon,
     theme: Theme,
     theme_editor: bool,
-    example_request: Option<(&'static str, &'static [u8])>,
+    // Index of the built-in format to load as an example
+    example_request: Option<usize>,
+    formats: Library,
+    formats_panel: FormatsPanel,
     // Agent System
     agent_system: AgentSystem,
     agents_open: bool,
@@ -111,14 +178,19 @@ const HELPTEXT_TAG: Cell = xeh_str_lit!("text");
 
 impl Default for TemplateApp {
     fn default() -> Self {
-        let xs = Self::xs_respawn();
+        let xs = Self::xs_respawn(true);
         let vars_boot_len = xs.var_list().len();
+        let lexicon = Lexicon::new(&xs);
         Self {
             xs,
             view_pos: 0,
             num_rows: 10,
             num_cols: 8,
+            hex_layout: HexLayout::default(),
             live_code: String::new(),
+            buffers: vec![Buffer::new("main", String::new())],
+            active_buffer: 0,
+            buffer_rename: String::new(),
             frozen_code: Vec::new(),
             trial_code: Some(Xstr::new()),
             last_dt: None,
@@ -128,7 +200,20 @@ impl Default for TemplateApp {
             insn_limit: Some(10_000_000),
             snapshot: None,
             bin_future: None,
-            input_binary: None,
+            compare_future: None,
+            ksy_future: None,
+            save_future: None,
+            pending_saves: Vec::new(),
+            inputs: Vec::new(),
+            active_input: 0,
+            bookmarks: BookmarkMap::new(),
+            bookmarks_panel: BookmarksPanel::default(),
+            overview: Overview::default(),
+            compare: Compare::default(),
+            hex_edit: HexEditor::default(),
+            read_ranges: Vec::new(),
+            read_ranges_key: (u64::MAX, 0, reads::InputId::default()),
+            track_reads: true,
             focus_on_code: true,
             rdebug_enabled: false,
             interval_enabled: false,
@@ -141,6 +226,16 @@ impl Default for TemplateApp {
             goto_open: false,
             goto_text: String::new(),
             goto_old_pos: None,
+            search: Search::default(),
+            inspector: Inspector::default(),
+            structure: StructureTree::default(),
+            export: Export::default(),
+            struct_gen: StructGen::default(),
+            hash_panel: HashPanel::default(),
+            disasm: Disassembly::default(),
+            frozen_highlight: None,
+            frozen_scroll: false,
+            live_cursor_jump: None,
             help: Help {
                 is_open: false,
                 mode: HelpMode::Hotkeys,
@@ -149,9 +244,13 @@ impl Default for TemplateApp {
                 live_cursor: None,
                 follow_cursor: false,
             },
+            completion: Completion::default(),
+            lexicon,
             theme: Theme::default(),
             theme_editor: false,
             example_request: None,
+            formats: Library::builtin(),
+            formats_panel: FormatsPanel::default(),
             agent_system: AgentSystem::default(),
             agents_open: false,
             todo_open: false,
@@ -188,11 +287,20 @@ impl TemplateApp {
         if let Some(storage) = cc.storage {
             app.theme = eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
             app.interval_word = eframe::get_value(storage, "interval").unwrap_or_default();
+            app.hex_layout = eframe::get_value(storage, "hex_layout").unwrap_or_default();
+            app.track_reads = eframe::get_value(storage, "track_reads").unwrap_or(true);
+            if !app.track_reads {
+                app.reload_state();
+            }
+            app.formats_panel.user_dir = eframe::get_value(storage, "formats_dir").unwrap_or_default();
+            #[cfg(not(target_arch = "wasm32"))]
+            if !app.formats_panel.user_dir.is_empty() {
+                app.reload_formats();
+            }
             app.workspaces = eframe::get_value(storage, "workspaces").unwrap_or_default();
             app.current_workspace = eframe::get_value(storage, "current_workspace").unwrap_or("Default".to_string());
-            if let Some(ws) = app.workspaces.get(&app.current_workspace) {
-                app.live_code = ws.code.clone();
-                app.agent_system.tasks = ws.tasks.clone();
+            if let Some(ws) = app.workspaces.get(&app.current_workspace).cloned() {
+                app.load_workspace(ws);
             }
             // Load agents
             if let Some(agents) = eframe::get_value::<HashMap<Uuid, crate::agent::Agent>>(storage, "agents") {
@@ -206,10 +314,15 @@ impl TemplateApp {
         return app;
     }
 
-    fn xs_respawn() -> Xstate {
+    fn xs_respawn(track_reads: bool) -> Xstate {
         let mut xs = Xstate::boot().unwrap();
         xs.intercept_stdout(true);
         xeh::d2_plugin::load(&mut xs).unwrap();
+        reads::install(&mut xs, track_reads).unwrap();
+        inputs::install(&mut xs).unwrap();
+        decompress::install(&mut xs).unwrap();
+        hash::install(&mut xs).unwrap();
+        output::install(&mut xs).unwrap();
         xs
     }
 
@@ -248,8 +361,17 @@ impl TemplateApp {
         context
     }
 
+    /// Bits shown in a row of the hex panel.
+    fn row_bits(&self) -> usize {
+        if self.hex_layout.bit_view.enabled {
+            self.hex_layout.bit_view.row_bits(self.num_cols)
+        } else {
+            self.num_cols * 8
+        }
+    }
+
     fn move_view(&mut self, nrows: isize) {
-        let n = (self.view_pos as isize + (nrows * self.num_cols as isize * 8)).max(0) as usize;
+        let n = (self.view_pos as isize + (nrows * self.row_bits() as isize)).max(0) as usize;
         self.view_pos = n.min(self.current_bstr().end());
     }
 
@@ -268,9 +390,570 @@ impl TemplateApp {
             .unwrap_or_default()
     }
 
-    fn binary_dropped(&mut self, s: Xbitstr) {
-        self.input_binary = Some(s.clone());
-        self.reload_state();
+    fn binary_dropped(&mut self, name: &str, s: Xbitstr) {
+        self.add_input(Input::new(name, s, None));
+        self.offer_formats();
+    }
+
+    /// Show the parsers matching the signature of the active input.
+    fn offer_formats(&mut self) {
+        let detected = match self.active() {
+            Some(input) => self.formats.detect(&input.data),
+            None => Vec::new(),
+        };
+        if !detected.is_empty() {
+            self.formats_panel.is_open = true;
+        }
+        self.formats_panel.detected = detected;
+    }
+
+    fn open_format(&mut self, index: usize) {
+        let Some(f) = self.formats.formats.get(index) else {
+            return;
+        };
+        let name = f.name.to_lowercase().replace(' ', "-");
+        let code = f.source.to_string();
+        self.new_buffer(&name, code);
+        self.focus_on_code = true;
+    }
+
+    /// Translate a Kaitai Struct spec into a new buffer, log what was left out.
+    fn import_ksy(&mut self, file_name: &str, text: &str) {
+        match kaitai::import(text) {
+            Ok(imported) => {
+                self.new_buffer(&imported.name, imported.source);
+                self.focus_on_code = true;
+                for what in &imported.unsupported {
+                    let msg = format!("{}: unsupported {}", file_name, what);
+                    self.frozen_code.push(FrozenStr::Log(msg));
+                }
+            }
+            Err(e) => {
+                let msg = format!("Failed to import {}: {}", file_name, e);
+                self.frozen_code.push(FrozenStr::Log(msg));
+            }
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn reload_formats(&mut self) {
+        let dir = self.formats_panel.user_dir.trim();
+        self.formats_panel.errors = if dir.is_empty() {
+            self.formats.formats.retain(|f| f.builtin);
+            Vec::new()
+        } else {
+            self.formats.load_dir(std::path::Path::new(dir))
+        };
+        self.offer_formats();
+    }
+
+    /// Input of the active tab.
+    fn active(&self) -> Option<&Input> {
+        self.inputs.get(self.active_input)
+    }
+
+    /// Index of the input the hex view shows, the scripts may select another one than the
+    /// active tab with `input/select`. None for a part opened with `open-input`, it's not editable.
+    fn shown_index(&self) -> Option<usize> {
+        let id = reads::InputId::of(self.current_bstr());
+        let is_shown = |i: &Input| reads::InputId::of(&i.data) == id;
+        match self.active() {
+            Some(input) if is_shown(input) => Some(self.active_input),
+            _ => self.inputs.iter().position(is_shown),
+        }
+    }
+
+    /// Input shown in the hex view, edits, bookmarks and hashes go to it.
+    fn input(&self) -> Option<&Input> {
+        self.shown_index().map(|i| &self.inputs[i])
+    }
+
+    /// Key of the bookmarks of the active input.
+    fn input_hash(&self) -> String {
+        match self.input() {
+            Some(input) => input.hash.clone(),
+            None => bookmarks::input_hash(&Xbitstr::default()),
+        }
+    }
+
+    /// Open the input in a new tab, or in place of the one with the same name.
+    fn add_input(&mut self, input: Input) {
+        match self.inputs.iter().position(|i| i.name == input.name) {
+            Some(i) => {
+                self.inputs[i] = input;
+                self.active_input = i;
+            }
+            None => {
+                self.inputs.push(input);
+                self.active_input = self.inputs.len() - 1;
+            }
+        }
+        self.input_changed();
+    }
+
+    fn select_input(&mut self, index: usize) {
+        if index == self.active_input || index >= self.inputs.len() {
+            return;
+        }
+        self.active_input = index;
+        self.view_pos = 0;
+        self.input_changed();
+    }
+
+    fn close_input(&mut self, index: usize) {
+        self.inputs.remove(index);
+        if self.active_input > index || self.active_input >= self.inputs.len() {
+            self.active_input = self.active_input.saturating_sub(1);
+        }
+        self.view_pos = 0;
+        self.input_changed();
+    }
+
+    fn input_changed(&mut self) {
+        self.hex_edit.reset();
+        self.overview.invalidate();
+        self.compare.invalidate();
+        self.hash_panel.invalidate();
+        self.reload_keeping_live_code();
+    }
+
+    /// Edit bytes of the loaded binary and re-run the scripts on the result.
+    fn patch_binary(&mut self, f: impl FnOnce(&mut HexEditor, &mut [u8]) -> bool) {
+        let Some(index) = self.shown_index() else {
+            return;
+        };
+        let bin = &self.inputs[index].data;
+        if bin.end() / 8 > binary::MAX_PATCH_BYTES {
+            let msg = format!("Input is too large to patch, limit {} bytes", binary::MAX_PATCH_BYTES);
+            self.frozen_code.push(FrozenStr::Log(msg));
+            return;
+        }
+        let mut bytes = binary::to_bytes(bin);
+        if f(&mut self.hex_edit, &mut bytes) {
+            self.inputs[index].patch(bytes);
+            self.overview.invalidate();
+            self.compare.invalidate();
+            self.hash_panel.invalidate();
+            self.reload_keeping_live_code();
+        }
+    }
+
+    fn save_binary_as(&mut self) {
+        let data = match self.input() {
+            Some(input) => binary::to_bytes_limited(&input.data),
+            None => binary::to_bytes_limited(self.current_bstr()),
+        };
+        let data = match data {
+            Ok(data) => data,
+            Err(e) => {
+                self.frozen_code.push(FrozenStr::Log(format!("Can't save the input: {}", e)));
+                return;
+            }
+        };
+        let name = self.input().map_or("input.bin", |i| i.name.as_str()).to_string();
+        self.pending_saves.push(output::SaveRequest { name, data });
+    }
+
+    /// Scroll the hex view just enough to make the bit position visible.
+    fn scroll_to(&mut self, pos: usize) {
+        let row_bits = self.row_bits();
+        let visible_bits = self.num_rows * row_bits;
+        if pos < self.view_pos {
+            self.view_pos = pos - pos % row_bits;
+        } else if pos >= self.view_pos + visible_bits {
+            let row = pos - pos % row_bits;
+            self.view_pos = row.saturating_sub(visible_bits - row_bits);
+        }
+    }
+
+    fn hex_edit_keys(&mut self, ctx: &egui::Context) {
+        let len = self.input().map(|i| i.data.end().div_ceil(8)).unwrap_or(0);
+        let ncols = self.num_cols as isize;
+        let delta = ctx.input(|i| {
+            if i.key_pressed(Key::ArrowLeft) {
+                -1
+            } else if i.key_pressed(Key::ArrowRight) {
+                1
+            } else if i.key_pressed(Key::ArrowUp) {
+                -ncols
+            } else if i.key_pressed(Key::ArrowDown) {
+                ncols
+            } else {
+                0
+            }
+        });
+        if delta != 0 {
+            if !ctx.input(|i| i.modifiers.shift) {
+                self.hex_edit.anchor = None;
+            } else if self.hex_edit.anchor.is_none() {
+                self.hex_edit.anchor = self.hex_edit.cursor;
+            }
+            self.hex_edit.move_cursor(delta, len);
+        }
+        if ctx.input(|i| i.key_pressed(Key::Escape)) {
+            self.hex_edit.deselect();
+            return;
+        }
+        let typed: String = ctx.input(|i| {
+            i.events
+                .iter()
+                .filter_map(|e| match e {
+                    Event::Text(t) => Some(t.as_str()),
+                    _ => None,
+                })
+                .collect()
+        });
+        if !typed.is_empty() {
+            self.patch_binary(|edit, bytes| edit.input(&typed, bytes));
+        }
+        if let Some(pos) = self.hex_edit.cursor {
+            self.scroll_to(pos * 8);
+        }
+    }
+
+    /// Draw a row of bit groups starting at the bit position, return the clicked bit.
+    fn ui_bit_row(
+        &self,
+        ui: &mut Ui,
+        bits: &[bool],
+        pos: usize,
+        offset: usize,
+        ranges: &[&VarRange],
+    ) -> Option<usize> {
+        let font_id = TextStyle::Monospace.resolve(ui.style());
+        let group = self.hex_layout.bit_view.group;
+        let mut clicked = None;
+        for g in 0..self.hex_layout.bit_view.groups_per_row(self.num_cols) {
+            let start = g * group;
+            if start >= bits.len() {
+                break;
+            }
+            let group_bits = &bits[start..bits.len().min(start + group)];
+            let mut job = text::LayoutJob::default();
+            job.append(" ", 0.0, TextFormat::simple(font_id.clone(), self.theme.comment));
+            for k in self.hex_layout.bit_view.display_order(group_bits.len()) {
+                let p = pos + start + k;
+                let mut format = TextFormat::simple(font_id.clone(), self.theme.code);
+                format.color = match ranges.iter().rev().find(|r| r.range.contains(&p)) {
+                    Some(r) => r.color,
+                    None if p < offset => self.theme.code_frozen,
+                    None => self.theme.code,
+                };
+                if p == offset {
+                    format.background = self.theme.selection_background;
+                } else if let Some(b) = self.current_bookmarks().iter().rev().find(|b| b.contains(p)) {
+                    format.background = b.color32().gamma_multiply(0.35);
+                }
+                if self.hex_edit.cursor == Some(p / 8) {
+                    format.underline = Stroke::new(1.0, self.theme.selection);
+                }
+                job.append(if group_bits[k] { "1" } else { "0" }, 0.0, format);
+            }
+            let resp = ui.add(Label::new(job).sense(Sense::click()));
+            let val = self.hex_layout.bit_view.value(group_bits);
+            let mut hint = format!(
+                "{}: {} 0x{:x}",
+                self.hex_offset_str(pos + start, 0),
+                val,
+                val
+            );
+            if let Some(r) = ranges.iter().rev().find(|r| r.range.contains(&(pos + start))) {
+                hint.push('\n');
+                hint.push_str(&match &r.name {
+                    Some(name) => format!("{} = {}", name, r.value),
+                    None => r.value.clone(),
+                });
+            }
+            if resp.on_hover_text(hint).clicked() {
+                clicked = Some(pos + start);
+            }
+        }
+        clicked
+    }
+
+    fn hex_cell_text(&self, text: RichText, byte: usize, ascii: bool) -> RichText {
+        let mut text = text;
+        if let Some(b) = self.current_bookmarks().iter().rev().find(|b| b.contains(byte * 8)) {
+            text = text.background_color(b.color32().gamma_multiply(0.35));
+        }
+        if self.hex_edit.is_modified(byte) {
+            text = text.color(self.theme.error);
+        }
+        if self.search.current_bytes().is_some_and(|r| r.contains(&byte)) {
+            text = text.background_color(self.theme.border);
+        }
+        if self.hex_edit.anchor.is_some()
+            && self.hex_edit.selection().is_some_and(|r| r.contains(&byte))
+        {
+            text = text.background_color(self.theme.selection_background);
+        }
+        if self.hex_edit.cursor == Some(byte) {
+            text = text.background_color(self.theme.selection_background);
+            if self.hex_edit.ascii == ascii {
+                text = text.underline();
+            }
+        }
+        text
+    }
+
+    /// Append code reading the interpretation at the bit position to the live code.
+    fn insert_read_snippet(&mut self, it: &inspector::Interpretation, pos: usize) {
+        let var = self.unique_var_name("x");
+        let has_vlq = self.lexicon.words.contains("decode-vlq")
+            || layouter::find_definition(&self.live_code, "decode-vlq").is_some();
+        let seek = Some(pos).filter(|&pos| pos != self.current_offset());
+        let code = inspector::snippet(it, &var, seek, has_vlq);
+        self.append_live_code(&code);
+    }
+
+    /// Append code reading the selected bytes into a new var.
+    fn generate_read_code(&mut self) {
+        let Some(sel) = self.hex_edit.selection() else {
+            return;
+        };
+        let var = self.unique_var_name("field");
+        let mut code = String::new();
+        if sel.start * 8 != self.current_offset() {
+            write!(code, "{} seek ", sel.start * 8).unwrap();
+        }
+        write!(code, "{} bytes read var {}", sel.len(), var).unwrap();
+        self.append_live_code(&code);
+    }
+
+    /// Name not taken by any var or word of the live code.
+    fn unique_var_name(&self, base: &str) -> String {
+        let vars: HashSet<String> = self.xs.var_list().iter().map(|(n, _)| n.to_string()).collect();
+        let mut var = base.to_string();
+        let mut n = 1;
+        while vars.contains(&var) || self.live_code.split_whitespace().any(|w| w == var) {
+            n += 1;
+            var = format!("{}{}", base, n);
+        }
+        var
+    }
+
+    fn append_live_code(&mut self, code: &str) {
+        if !self.live_code.is_empty() && !self.live_code.ends_with('\n') {
+            self.live_code.push('\n');
+        }
+        self.live_code.push_str(code);
+        self.live_code.push('\n');
+        self.focus_on_code = true;
+    }
+
+    fn selected_bytes(&self) -> Vec<u8> {
+        let Some(sel) = self.hex_edit.selection() else {
+            return Vec::new();
+        };
+        binary::bytes_at(self.current_bstr(), sel.start, sel.len())
+    }
+
+    /// Open the decompressed selection as a child input of the active one.
+    fn decompress_selection(&mut self, codec: Codec) {
+        let (Some(sel), Some(parent)) = (self.hex_edit.selection(), self.input()) else {
+            return;
+        };
+        let parent_name = parent.name.clone();
+        // a lone cursor byte marks the start of a stream running to its end
+        let len = if sel.len() == 1 {
+            binary::MAX_COPY_BYTES
+        } else {
+            sel.len()
+        };
+        let bytes = binary::bytes_at(&parent.data, sel.start, len);
+        match decompress::decompress(codec, &bytes) {
+            Ok((data, len)) => {
+                let name = format!("{}@{:x}.{}", parent_name, sel.start, codec.name());
+                let mut input = Input::new(&name, Xbitstr::from(data), None);
+                input.parent = Some(Parent {
+                    name: parent_name,
+                    range: sel.start * 8..(sel.start + len) * 8,
+                    codec: codec.name().to_string(),
+                });
+                self.add_input(input);
+                self.offer_formats();
+            }
+            Err(e) => {
+                let msg = format!("Decompress as {} failed: {}", codec.name(), e);
+                self.frozen_code.push(FrozenStr::Log(msg));
+            }
+        }
+    }
+
+    /// Recompute the hashes when the input, the selection or the scope have changed.
+    fn update_hashes(&mut self) {
+        let sel = self.hex_edit.selection().filter(|_| !self.hash_panel.whole_input);
+        let bs = self.input().map_or(self.current_bstr(), |i| &i.data);
+        let key = (reads::InputId::of(bs), sel.clone(), self.hash_panel.whole_input);
+        if key == self.hash_panel.key {
+            return;
+        }
+        let data = match sel {
+            Some(sel) => Ok(binary::bytes_at(bs, sel.start, sel.len())),
+            None => binary::to_bytes_limited(bs),
+        };
+        self.hash_panel.update(data);
+        self.hash_panel.key = key;
+    }
+
+    /// Decode the range again when it, the input or the labels have changed.
+    fn update_disasm(&mut self) {
+        let vars = self.xs.var_list();
+        let key = (
+            self.input_hash(),
+            self.disasm.cpu,
+            self.disasm.start.clone(),
+            self.disasm.len.clone(),
+            self.disasm.origin.clone(),
+            reads::generation(),
+            vars.len(),
+            self.bookmarks_panel.revision,
+        );
+        if key == self.disasm.key {
+            return;
+        }
+        // labels from bookmarks and from variables holding an offset, e.g. `prg-offset`
+        let mut labels: HashMap<usize, String> = self
+            .current_bookmarks()
+            .iter()
+            .map(|b| (b.start / 8, b.name.clone()))
+            .collect();
+        // a value read from the input is a byte offset stored in the file (`pe-offset`),
+        // any other is a bit position taken with `offset`, without read tracking the unit is unknown
+        if self.track_reads {
+            let var_reads = reads::var_reads(&reads::snapshot(), &vars);
+            for (k, (name, val)) in vars.iter().enumerate().skip(self.vars_boot_len) {
+                if let (true, Ok(n)) = (name.ends_with("offset"), val.to_usize()) {
+                    let pos = if var_reads[k].is_some() { n } else { n / 8 };
+                    labels.insert(pos, name.to_string());
+                }
+            }
+        }
+        let (start, origin) = self.disasm.base();
+        let len = disasm::parse_hex(&self.disasm.len).unwrap_or(usize::MAX);
+        let bytes: Vec<u8> = match self.current_bstr().seek(start * 8) {
+            Some(bs) => bs.iter8().take(len.min(disasm::MAX_BYTES)).map(|(b, _)| b).collect(),
+            None => Vec::new(),
+        };
+        self.disasm.lines = disasm::decode(self.disasm.cpu, &bytes, origin);
+        self.disasm.labels = labels;
+        self.disasm.input_len = self.current_bstr().end() / 8;
+        self.disasm.key = key;
+    }
+
+    /// Inputs the active one was decompressed from, the outermost first.
+    fn input_breadcrumbs(&self) -> Vec<(usize, &Parent)> {
+        let mut crumbs = Vec::new();
+        let mut parent = self.active().and_then(|i| i.parent.as_ref());
+        while let Some(p) = parent {
+            let Some(index) = self.inputs.iter().position(|i| i.name == p.name) else {
+                break;
+            };
+            if crumbs.len() >= self.inputs.len() {
+                break;
+            }
+            crumbs.push((index, p));
+            parent = self.inputs[index].parent.as_ref();
+        }
+        crumbs.reverse();
+        crumbs
+    }
+
+    fn selection_status(&self) -> Option<String> {
+        let sel = self.hex_edit.selection()?;
+        Some(format!(
+            "SEL {}..{} ({} bytes)",
+            self.hex_offset_str(sel.start * 8, 0),
+            self.hex_offset_str(sel.end * 8, 0),
+            sel.len()
+        ))
+    }
+
+    /// Match the logged reads with vars again if anything was evaluated since.
+    fn update_read_ranges(&mut self) {
+        let vars = self.xs.var_list();
+        let input = reads::InputId::of(self.current_bstr());
+        let key = (reads::generation(), vars.len(), input);
+        if key == self.read_ranges_key {
+            return;
+        }
+        let bs = self.current_bstr();
+        self.read_ranges = reads::assign(&reads::snapshot(), &vars, self.vars_boot_len, bs);
+        self.read_ranges_key = key;
+    }
+
+    fn export_results(&mut self, ctx: &egui::Context, action: ExportAction) {
+        let vars = self.xs.var_list();
+        let user_vars = vars.get(self.vars_boot_len..).unwrap_or_default();
+        let results = export::results(user_vars, self.xs.data_slice(), self.export.encoding);
+        match export::render(self.export.format, &results) {
+            Ok(text) => match action {
+                ExportAction::Copy => ctx.copy_text(text),
+                ExportAction::Save => {
+                    let name = format!("results.{}", self.export.format.extension());
+                    let data = text.into_bytes();
+                    self.pending_saves.push(output::SaveRequest { name, data });
+                }
+            },
+            Err(e) => self.frozen_code.push(FrozenStr::Log(format!("Export failed: {}", e))),
+        }
+    }
+
+    /// Regenerate the struct source when the reads, the language or the name have changed.
+    fn update_struct_gen(&mut self) {
+        if self.struct_gen.name.trim().is_empty() {
+            self.struct_gen.name = self.buffers[self.active_buffer].name.clone();
+        }
+        let vars = self.xs.var_list();
+        let key = (
+            reads::generation(),
+            vars.len(),
+            self.struct_gen.lang,
+            self.struct_gen.name.clone(),
+        );
+        if key == self.struct_gen.key {
+            return;
+        }
+        let fields = codegen::fields(
+            &reads::snapshot(),
+            &vars,
+            self.vars_boot_len,
+            self.current_bstr(),
+        );
+        self.struct_gen.generate(&fields);
+        self.struct_gen.key = key;
+    }
+
+    fn open_struct_gen(&mut self, lang: codegen::Lang) {
+        self.struct_gen.lang = lang;
+        self.struct_gen.is_open = true;
+    }
+
+    /// Rebuild the structure tree when the script state has changed.
+    fn update_structure(&mut self) {
+        let vars = self.xs.var_list();
+        let input = reads::InputId::of(self.current_bstr());
+        let key = (reads::generation(), vars.len(), self.xs.data_depth(), input);
+        if key == self.structure.key {
+            return;
+        }
+        self.structure.roots = tree::build(
+            &vars,
+            self.vars_boot_len,
+            self.xs.data_slice(),
+            &reads::snapshot(),
+            self.current_bstr(),
+        );
+        self.structure.key = key;
+    }
+
+    /// Select the bytes covering the bit range and scroll to them.
+    fn select_bits(&mut self, range: Range<usize>) {
+        if self.input().is_some() {
+            self.hex_edit.select(range.start / 8, false);
+            self.hex_edit.extend((range.end.max(range.start + 1) - 1) / 8);
+        }
+        self.scroll_to(range.start);
     }
 
     fn collect_frozen_code(&self) -> String {
@@ -288,24 +971,39 @@ impl TemplateApp {
 
     fn reload_state(&mut self) {
         let buf = self.collect_frozen_code();
-        self.xs = Self::xs_respawn();
+        self.xs = Self::xs_respawn(self.track_reads);
         self.canvas = Canvas::new();
         self.live_code = buf;
         self.frozen_code.clear();
-        if let Some(bin) = &self.input_binary {
-            let _ = self.xs.set_binary_input(bin.clone());
-        }
+        self.frozen_highlight = None;
+        self.apply_input();
         self.vars_boot_len = self.xs.var_list().len();
+        self.lexicon.update(&self.xs, self.vars_boot_len);
         if self.is_trial() {
             self.trial_code = Some(Xstr::new());
             self.snapshot();
         } else {
             self.snapshot = None;
         }
+        output::take_pending();
+    }
+
+    /// Reload without losing the code being typed, reload_state replaces it with the frozen code.
+    fn reload_keeping_live_code(&mut self) {
+        let live_code = std::mem::take(&mut self.live_code);
+        self.reload_state();
+        self.live_code.push_str(&live_code);
+    }
+
+    fn apply_input(&mut self) {
+        inputs::publish(&self.inputs);
+        if let Some(input) = self.inputs.get(self.active_input) {
+            let _ = self.xs.set_binary_input(input.data.clone());
+        }
     }
 
     fn snapshot(&mut self) {
-        self.snapshot = Some((self.xs.clone(), self.frozen_code.to_owned()));
+        self.snapshot = Some((self.xs.clone(), self.frozen_code.to_owned(), reads::snapshot()));
     }
 
     fn rollback(&mut self) {
@@ -314,9 +1012,111 @@ impl TemplateApp {
         } else {
             self.snapshot.take()
         };
-        if let Some((xs, frozen)) = old_state {
+        if let Some((xs, frozen, read_log)) = old_state {
             self.xs = xs;
             self.frozen_code = frozen;
+            reads::restore(read_log);
+            self.frozen_highlight = None;
+        }
+        // only the code frozen by Run may save files, not an evaluation rolled back
+        output::take_pending();
+    }
+
+    fn workspace_snapshot(&self, name: &str) -> Workspace {
+        let mut buffers = self.buffers.clone();
+        buffers[self.active_buffer].code = self.live_code.clone();
+        let mut ws = Workspace::new(name, buffers, self.active_buffer, self.agent_system.tasks.clone());
+        ws.bookmarks = self.bookmarks.clone();
+        ws.inputs = self.inputs.iter().map(Input::saved).collect();
+        ws.active_input = self.active_input;
+        ws
+    }
+
+    fn save_workspace(&mut self) {
+        let ws = self.workspace_snapshot(&self.current_workspace);
+        self.workspaces.insert(self.current_workspace.clone(), ws);
+    }
+
+    fn load_workspace(&mut self, mut ws: Workspace) {
+        ws.normalize();
+        self.buffers = ws.buffers;
+        self.active_buffer = ws.active_buffer;
+        self.agent_system.tasks = ws.tasks;
+        self.bookmarks = ws.bookmarks;
+        self.bookmarks_panel.revision += 1;
+        self.inputs.clear();
+        for saved in &ws.inputs {
+            match saved.restore() {
+                Ok(input) => self.inputs.push(input),
+                Err(e) => {
+                    let msg = format!("Failed to restore input {}: {}", saved.name, e);
+                    self.frozen_code.push(FrozenStr::Log(msg));
+                }
+            }
+        }
+        self.active_input = ws.active_input.min(self.inputs.len().saturating_sub(1));
+        self.hex_edit.reset();
+        self.overview.invalidate();
+        self.compare.invalidate();
+        self.hash_panel.invalidate();
+        // the frozen code and the reads belong to the previous workspace
+        self.frozen_code.clear();
+        self.reload_state();
+        self.live_code = self.buffers[self.active_buffer].code.clone();
+    }
+
+    fn current_bookmarks(&self) -> &[Bookmark] {
+        self.bookmarks.get(&self.input_hash()).map(|v| v.as_slice()).unwrap_or_default()
+    }
+
+    /// Bookmark the selected bytes, or the top of the view if nothing is selected.
+    fn add_bookmark(&mut self) {
+        let (start, len) = match self.hex_edit.selection() {
+            Some(sel) => (sel.start * 8, sel.len() * 8),
+            None => (self.view_pos, 8),
+        };
+        self.bookmarks_panel.revision += 1;
+        let list = self.bookmarks.entry(self.input_hash()).or_default();
+        let mut n = list.len() + 1;
+        while list.iter().any(|b| b.name == format!("mark{}", n)) {
+            n += 1;
+        }
+        let color = reads::range_color(n).to_array();
+        list.push(Bookmark {
+            name: format!("mark{}", n),
+            start,
+            len,
+            color: [color[0], color[1], color[2]],
+            comment: String::new(),
+        });
+        self.bookmarks_panel.is_open = true;
+    }
+
+    fn switch_buffer(&mut self, index: usize) {
+        if index == self.active_buffer || index >= self.buffers.len() {
+            return;
+        }
+        self.buffers[self.active_buffer].code = std::mem::take(&mut self.live_code);
+        self.active_buffer = index;
+        self.live_code = self.buffers[index].code.clone();
+    }
+
+    fn new_buffer(&mut self, name: &str, code: String) {
+        let name = unique_buffer_name(&self.buffers, name);
+        self.buffers.push(Buffer::new(&name, code));
+        self.switch_buffer(self.buffers.len() - 1);
+    }
+
+    fn close_buffer(&mut self, index: usize) {
+        if self.buffers.len() < 2 || index >= self.buffers.len() {
+            return;
+        }
+        self.buffers.remove(index);
+        if index < self.active_buffer {
+            self.active_buffer -= 1;
+        } else if index == self.active_buffer {
+            self.active_buffer = index.min(self.buffers.len() - 1);
+            self.live_code = self.buffers[self.active_buffer].code.clone();
         }
     }
 
@@ -345,15 +1145,25 @@ impl TemplateApp {
         let mut help_clicked = false;
         let mut canvas_clicked = false;
         let mut open_clicked = false;
+        let mut save_binary_clicked = false;
+        let mut compare_clicked = false;
         let mut goto_clicked = false;
+        let mut find_clicked = false;
         let mut vars_clicked = false;
         let mut unfreeze_clicked = false;
         let win_rect = ctx.available_rect();
+        self.update_read_ranges();
 
         if let Some(action) = self.palette.show(ctx) {
              match action {
                  CommandAction::ToggleTheme => self.theme_editor = !self.theme_editor,
                  CommandAction::OpenBinary => open_clicked = true,
+                 CommandAction::SaveBinary => save_binary_clicked = true,
+                 CommandAction::CompareBinary => compare_clicked = true,
+                 CommandAction::ExportResults => self.export.is_open = true,
+                 CommandAction::GenerateC => self.open_struct_gen(codegen::Lang::C),
+                 CommandAction::GenerateRust => self.open_struct_gen(codegen::Lang::Rust),
+                 CommandAction::ImportKaitai => self.ksy_future = Some(Self::pick_binary()),
                  CommandAction::ToggleCanvas => canvas_clicked = true,
                  CommandAction::ToggleBytecode => self.bytecode_open = !self.bytecode_open,
                  CommandAction::ToggleVariables => vars_clicked = true,
@@ -376,18 +1186,21 @@ impl TemplateApp {
                  },
                  CommandAction::ConnectNetwork => self.collab_open = !self.collab_open,
                  CommandAction::ToggleWorkspaces => self.workspace_open = !self.workspace_open,
-                 CommandAction::SaveWorkspace => {
-                    let ws = Workspace {
-                        name: self.current_workspace.clone(),
-                        code: self.live_code.clone(),
-                        tasks: self.agent_system.tasks.clone(),
-                    };
-                    self.workspaces.insert(self.current_workspace.clone(), ws);
-                 },
+                 CommandAction::SaveWorkspace => self.save_workspace(),
                  #[cfg(not(target_arch = "wasm32"))]
                  CommandAction::ToggleFileExplorer => {
                      self.file_explorer.is_open = !self.file_explorer.is_open;
                  }
+                 CommandAction::ToggleFind => find_clicked = true,
+                 CommandAction::ToggleInspector => self.inspector.is_open = !self.inspector.is_open,
+                 CommandAction::ToggleDisassembly => self.disasm.is_open = !self.disasm.is_open,
+                 CommandAction::ToggleHash => self.hash_panel.is_open = !self.hash_panel.is_open,
+                 CommandAction::ToggleStructure => self.structure.is_open = !self.structure.is_open,
+                 CommandAction::ToggleFormats => self.formats_panel.is_open = !self.formats_panel.is_open,
+                 CommandAction::ToggleOverview => self.overview.is_open = !self.overview.is_open,
+                 CommandAction::ToggleBookmarks => {
+                     self.bookmarks_panel.is_open = !self.bookmarks_panel.is_open;
+                 }
              }
         }
 
@@ -402,10 +1215,21 @@ impl TemplateApp {
                 CollabMessage::Hello { id, name } => {
                     self.collab_system.peers.insert(id, name);
                 }
-                CollabMessage::Code { text } => {
-                    if self.live_code != text {
-                        self.live_code = text;
-                        code_changed_remotely = true;
+                CollabMessage::Code { buffer, text } => {
+                    let index = if buffer.is_empty() {
+                        Some(self.active_buffer)
+                    } else {
+                        self.buffers.iter().position(|b| b.name == buffer)
+                    };
+                    match index {
+                        Some(i) if i == self.active_buffer => {
+                            if self.live_code != text {
+                                self.live_code = text;
+                                code_changed_remotely = true;
+                            }
+                        }
+                        Some(i) => self.buffers[i].code = text,
+                        None => self.buffers.push(Buffer::new(&buffer, text)),
                     }
                 }
                 CollabMessage::AgentUpdate { agents, tasks } => {
@@ -599,8 +1423,9 @@ impl TemplateApp {
             });
         self.todo_open = todo_open;
 
+        let mut workspace_open = self.workspace_open;
         egui::Window::new("Workspaces")
-            .open(&mut self.workspace_open)
+            .open(&mut workspace_open)
             .default_pos(pos2(win_rect.center().x, win_rect.center().y))
             .show(ctx, |ui| {
                 ui.horizontal(|ui| {
@@ -610,11 +1435,7 @@ impl TemplateApp {
                          let new_name = format!("{}-copy", self.current_workspace);
                          if !self.workspaces.contains_key(&new_name) {
                              // Save current state to the new workspace
-                             let ws = Workspace {
-                                 name: new_name.clone(),
-                                 code: self.live_code.clone(),
-                                 tasks: self.agent_system.tasks.clone(),
-                             };
+                             let ws = self.workspace_snapshot(&new_name);
                              self.workspaces.insert(new_name.clone(), ws);
                              // Switch to it
                              self.current_workspace = new_name;
@@ -640,12 +1461,7 @@ impl TemplateApp {
                 }
                 if let Some(name) = to_switch {
                     // Save current
-                    let ws = Workspace {
-                        name: self.current_workspace.clone(),
-                        code: self.live_code.clone(),
-                        tasks: self.agent_system.tasks.clone(),
-                    };
-                    self.workspaces.insert(self.current_workspace.clone(), ws);
+                    self.save_workspace();
 
                     // Clear pending reviews and agent state to prevent cross-workspace pollution
                     self.pending_reviews.clear();
@@ -657,9 +1473,8 @@ impl TemplateApp {
 
                     // Load new
                     self.current_workspace = name;
-                    if let Some(ws) = self.workspaces.get(&self.current_workspace) {
-                        self.live_code = ws.code.clone();
-                        self.agent_system.tasks = ws.tasks.clone();
+                    if let Some(ws) = self.workspaces.get(&self.current_workspace).cloned() {
+                        self.load_workspace(ws);
                     }
                 }
                 if let Some(name) = to_delete {
@@ -673,26 +1488,25 @@ impl TemplateApp {
                          let name = self.new_workspace_name.clone();
                          if !self.workspaces.contains_key(&name) {
                              // Save current
-                             let ws = Workspace {
-                                 name: self.current_workspace.clone(),
-                                 code: self.live_code.clone(),
-                                 tasks: self.agent_system.tasks.clone(),
-                             };
-                             self.workspaces.insert(self.current_workspace.clone(), ws);
+                             self.save_workspace();
 
                              // Create new (empty)
+                             let ws = Workspace::new(&name, Vec::new(), 0, Vec::new());
                              self.current_workspace = name.clone();
-                             self.live_code.clear();
-                             self.agent_system.tasks.clear();
-                             self.workspaces.insert(name, Workspace { name: self.current_workspace.clone(), ..Default::default() });
+                             self.load_workspace(ws.clone());
+                             self.workspaces.insert(name, ws);
                              self.new_workspace_name.clear();
                          }
                      }
                 });
             });
+        self.workspace_open = workspace_open;
 
+        let mut var_clicked = None;
+        let mut save_clicked = None;
+        let mut vars_open = self.vars_open;
         egui::Window::new("Variables")
-            .open(&mut self.vars_open)
+            .open(&mut vars_open)
             .default_pos(pos2(win_rect.right() - 200.0, 200.0))
             .resizable(true)
             .vscroll(true)
@@ -709,12 +1523,40 @@ impl TemplateApp {
                 let lst = self.xs.var_list();
                 let n = lst.len().checked_sub(self.vars_boot_len).unwrap_or(0);
                 for (name, val) in lst.iter().rev().take(n) {
+                    let range = self
+                        .read_ranges
+                        .iter()
+                        .find(|r| r.name.as_deref() == Some(name.as_str()));
                     ui.horizontal(|ui| {
-                        ui.colored_label(self.theme.text, name.to_string());
+                        let color = range.map(|r| r.color).unwrap_or(self.theme.text);
+                        let label = Label::new(RichText::new(name.to_string()).color(color));
+                        let resp = ui.add(label.sense(Sense::click()));
                         ui.colored_label(self.theme.code_frozen, val.format_safe());
+                        if let Ok(bs) = val.bitstr() {
+                            if save_button(ui, bs).clicked() {
+                                save_clicked = Some((format!("{}.bin", name), bs.clone()));
+                            }
+                        }
+                        if let Some(r) = range {
+                            let resp = resp.on_hover_text(format!(
+                                "{} .. {}",
+                                self.hex_offset_str(r.range.start, 0),
+                                self.hex_offset_str(r.range.end, 0)
+                            ));
+                            if resp.clicked() {
+                                var_clicked = Some(r.range.start);
+                            }
+                        }
                     });
                 }
             });
+        self.vars_open = vars_open;
+        if let Some(pos) = var_clicked {
+            self.scroll_to(pos);
+        }
+        if let Some((name, bs)) = save_clicked {
+            self.save_bitstr(&name, &bs);
+        }
 
         egui::Window::new("Bytecode")
             .open(&mut self.bytecode_open)
@@ -793,7 +1635,8 @@ impl TemplateApp {
                     xs.eval(s.into())?;
                     xs.pop_data()?.to_xint()
                 };
-                match evalgoto(&self.goto_text) {
+                let goto_text = bookmarks::resolve(&self.goto_text, self.current_bookmarks());
+                match evalgoto(&goto_text) {
                     Ok(n) => {
                         let bs = self.current_bstr().clone();
                         if n < 0 {
@@ -821,6 +1664,205 @@ impl TemplateApp {
             }
         }
 
+        let mut search = std::mem::take(&mut self.search);
+        let mut search_open = search.is_open;
+        Window::new("Find")
+            .open(&mut search_open)
+            .show(ctx, |ui| {
+                let (run, jump) = search.ui(ui, &self.theme, |pos| self.hex_offset_str(pos, 0));
+                if run {
+                    let bs = self.current_bstr();
+                    match binary::to_bytes_limited(bs) {
+                        Ok(bytes) => search.run(&bytes, bs.start(), bs.end() - bs.start()),
+                        Err(e) => search.error = Some(e),
+                    }
+                    if let Some(pos) = search.next(self.view_pos) {
+                        self.view_pos = pos;
+                    }
+                }
+                if let Some(pos) = jump {
+                    self.view_pos = pos;
+                }
+            });
+        search.is_open = search_open;
+        self.search = search;
+
+        if self.compare.is_open {
+            let bs = match self.input() {
+                Some(input) => input.data.clone(),
+                None => self.current_bstr().clone(),
+            };
+            self.compare.update(|| binary::to_bytes_limited(&bs));
+        }
+        let mut compare_open = self.compare.is_open;
+        Window::new(format!("Compare: {}", self.compare.name))
+            .id(Id::new("compare"))
+            .open(&mut compare_open)
+            .show(ctx, |ui| {
+                self.compare.ui(ui, &self.theme);
+            });
+        self.compare.is_open = compare_open;
+        if let Some(pos) = self.compare.sync(self.view_pos) {
+            self.view_pos = pos;
+        }
+
+        let mut panel = std::mem::take(&mut self.bookmarks_panel);
+        let mut bookmarks_open = panel.is_open;
+        let mut bookmark_action = None;
+        let input_hash = self.input_hash();
+        let mut list = self.bookmarks.remove(&input_hash).unwrap_or_default();
+        Window::new("Bookmarks")
+            .open(&mut bookmarks_open)
+            .default_pos(pos2(win_rect.right() - 200.0, 300.0))
+            .show(ctx, |ui| {
+                let fmt = |pos| self.hex_offset_str(pos, 0);
+                bookmark_action = panel.ui(ui, &self.theme, &mut list, fmt);
+            });
+        if !list.is_empty() {
+            self.bookmarks.insert(input_hash, list);
+        }
+        panel.is_open = bookmarks_open;
+        self.bookmarks_panel = panel;
+        match bookmark_action {
+            Some(BookmarkAction::Add) => self.add_bookmark(),
+            Some(BookmarkAction::Jump(pos)) => self.view_pos = pos,
+            None => (),
+        }
+
+        let mut inspector_open = self.inspector.is_open;
+        Window::new("Data Inspector")
+            .open(&mut inspector_open)
+            .default_pos(pos2(win_rect.right() - 200.0, 400.0))
+            .show(ctx, |ui| {
+                let pos = self.hex_edit.cursor.map(|byte| byte * 8 + self.inspector.bit);
+                let items = match pos.and_then(|pos| self.current_bstr().seek(pos)) {
+                    Some(bs) => {
+                        let bytes: Vec<u8> =
+                            bs.iter8().take(inspector::MAX_BYTES).map(|(b, _)| b).collect();
+                        inspector::interpret(&bytes)
+                    }
+                    None => Vec::new(),
+                };
+                if let Some(pos) = pos {
+                    ui.colored_label(
+                        self.theme.comment,
+                        format!("at {}", self.hex_offset_str(pos, 0)),
+                    );
+                }
+                if let Some(it) = self.inspector.ui(ui, &self.theme, &items) {
+                    self.insert_read_snippet(it, pos.unwrap_or_default());
+                }
+            });
+        self.inspector.is_open = inspector_open;
+
+        let mut formats_open = self.formats_panel.is_open;
+        let mut format_action = None;
+        Window::new("Formats")
+            .open(&mut formats_open)
+            .default_pos(pos2(win_rect.right() - 200.0, 100.0))
+            .show(ctx, |ui| {
+                format_action = self.formats_panel.ui(ui, &self.theme, &self.formats);
+            });
+        self.formats_panel.is_open = formats_open;
+        match format_action {
+            Some(FormatAction::Open(i)) => self.open_format(i),
+            #[cfg(not(target_arch = "wasm32"))]
+            Some(FormatAction::Reload) => self.reload_formats(),
+            None => (),
+        }
+
+        let mut export_open = self.export.is_open;
+        let mut export_action = None;
+        Window::new("Export Results")
+            .open(&mut export_open)
+            .default_pos(pos2(win_rect.right() - 200.0, 150.0))
+            .show(ctx, |ui| {
+                export_action = self.export.ui(ui, &self.theme);
+            });
+        self.export.is_open = export_open;
+        if let Some(action) = export_action {
+            self.export_results(ctx, action);
+        }
+
+        if self.struct_gen.is_open {
+            self.update_struct_gen();
+        }
+        let mut struct_gen_open = self.struct_gen.is_open;
+        let mut struct_gen_action = None;
+        Window::new("Struct Layout")
+            .open(&mut struct_gen_open)
+            .default_pos(pos2(win_rect.right() - 200.0, 200.0))
+            .resizable(true)
+            .show(ctx, |ui| {
+                struct_gen_action = self.struct_gen.ui(ui, &self.theme);
+            });
+        self.struct_gen.is_open = struct_gen_open;
+        match struct_gen_action {
+            Some(StructGenAction::Copy) => ctx.copy_text(self.struct_gen.text.clone()),
+            Some(StructGenAction::Save) => {
+                let name = format!("{}.{}", self.struct_gen.name, self.struct_gen.extension());
+                let data = self.struct_gen.text.clone().into_bytes();
+                self.pending_saves.push(output::SaveRequest { name, data });
+            }
+            None => (),
+        }
+
+        if self.hash_panel.is_open {
+            self.update_hashes();
+        }
+        let mut hash_open = self.hash_panel.is_open;
+        Window::new("Hash")
+            .open(&mut hash_open)
+            .default_pos(pos2(win_rect.right() - 200.0, 300.0))
+            .show(ctx, |ui| {
+                self.hash_panel.ui(ui, &self.theme);
+            });
+        self.hash_panel.is_open = hash_open;
+
+        if self.disasm.is_open {
+            self.update_disasm();
+        }
+        let mut disasm_open = self.disasm.is_open;
+        let mut disasm_action = None;
+        Window::new("Disassembly")
+            .open(&mut disasm_open)
+            .default_pos(pos2(win_rect.right() - 200.0, 350.0))
+            .resizable(true)
+            .show(ctx, |ui| {
+                disasm_action = self.disasm.ui(ui, &self.theme);
+            });
+        self.disasm.is_open = disasm_open;
+        match disasm_action {
+            Some(DisasmAction::Goto(offset)) => self.select_bits(offset * 8..offset * 8 + 8),
+            Some(DisasmAction::FromSelection) => {
+                if let Some(sel) = self.hex_edit.selection() {
+                    self.disasm.start = format!("{:x}", sel.start);
+                    self.disasm.len = format!("{:x}", sel.len());
+                }
+            }
+            None => (),
+        }
+
+        if self.structure.is_open {
+            self.update_structure();
+        }
+        let mut structure = std::mem::take(&mut self.structure);
+        let mut structure_open = structure.is_open;
+        let mut node_clicked = None;
+        Window::new("Structure")
+            .open(&mut structure_open)
+            .default_pos(pos2(win_rect.right() - 200.0, 250.0))
+            .resizable(true)
+            .show(ctx, |ui| {
+                let fmt = |pos| self.hex_offset_str(pos, 0);
+                node_clicked = structure.ui(ui, &self.theme, fmt);
+            });
+        structure.is_open = structure_open;
+        self.structure = structure;
+        if let Some(range) = node_clicked {
+            self.select_bits(range);
+        }
+
         let help_pos = pos2(win_rect.width() * 0.25, win_rect.height() * 0.25);
         egui::Window::new("Help")
             .open(&mut self.help.is_open)
@@ -868,8 +1910,17 @@ impl TemplateApp {
                                 add(ui, "Debugger - Enable Reverse Next", "(Esc, Y)");
                                 add(ui, "Hex - Scroll Up", "(Esc, Arrow Up)");
                                 add(ui, "Hex - Scroll Down", "(Esc, Arrow Down)");
+                                add(ui, "Hex - Page Up/Down", "(Esc, PageUp) / (Esc, PageDown)");
                                 add(ui, "Hex - Go To...", "(Esc, G)");
+                                add(ui, "Hex - Find...", "(Esc, F)");
+                                add(ui, "Hex - Find Next/Previous", "(F3) / (Shift+F3)");
+                                add(ui, "Compare - Next/Previous Difference", "(F8) / (Shift+F8)");
+                                add(ui, "Hex - Edit Byte", "(Click) then type hex digits or ASCII");
+                                add(ui, "Hex - Select Range", "(Shift+Click) or (Shift+Arrows)");
+                                add(ui, "Hex - Leave Edit Mode", "(Esc)");
+                                add(ui, "Hex - Undo/Redo Patch", "(Cmd/Ctrl+Z) / (Cmd/Ctrl+Y)");
                                 add(ui, "Focus on Code", "(Esc, E)");
+                                add(ui, "Code - Complete Word", "(Ctrl+Space), accept with (Tab) or (Enter)");
                                 add(ui, "Canvas - Show", "(Esc, M)");
                                 add(ui, "Help - Show", "(Esc, H)");
                                 add(ui, "Command Palette", "(Cmd/Ctrl+Shift+P)");
@@ -878,6 +1929,11 @@ impl TemplateApp {
                                     self.theme.text,
                                     "Open binary file with Drag and Drop",
                                 );
+                                ui.colored_label(
+                                    self.theme.text,
+                                    "Hover a word to see its documentation",
+                                );
+                                add(ui, "Go to word definition", "(Cmd/Ctrl+Click)");
                             });
                     }
                     HelpMode::Index => {
@@ -977,6 +2033,22 @@ impl TemplateApp {
                         open_clicked = true;
                         ui.close_menu();
                     }
+                    if ui.button(self.menu_text("Save Binary As...")).clicked() {
+                        save_binary_clicked = true;
+                        ui.close_menu();
+                    }
+                    if ui.button(self.menu_text("Compare With...")).clicked() {
+                        compare_clicked = true;
+                        ui.close_menu();
+                    }
+                    if ui.button(self.menu_text("Export Results...")).clicked() {
+                        self.export.is_open = true;
+                        ui.close_menu();
+                    }
+                    if ui.button(self.menu_text("Import Kaitai Struct...")).clicked() {
+                        self.ksy_future = Some(Self::pick_binary());
+                        ui.close_menu();
+                    }
                 });
                 ui.menu_button("Network", |ui| {
                     if ui.button(self.menu_text("Connection...")).clicked() {
@@ -989,10 +2061,50 @@ impl TemplateApp {
                         goto_clicked = true;
                         ui.close_menu();
                     }
+                    if ui.button(self.menu_text("Find...")).clicked() {
+                        find_clicked = true;
+                        ui.close_menu();
+                    }
+                    if ui.button(self.menu_text("Overview")).clicked() {
+                        self.overview.is_open = !self.overview.is_open;
+                        ui.close_menu();
+                    }
+                    if ui.button(self.menu_text("Bookmarks")).clicked() {
+                        self.bookmarks_panel.is_open = !self.bookmarks_panel.is_open;
+                        ui.close_menu();
+                    }
+                    if ui.button(self.menu_text("Data Inspector")).clicked() {
+                        self.inspector.is_open = !self.inspector.is_open;
+                        ui.close_menu();
+                    }
+                    if ui.button(self.menu_text("Formats")).clicked() {
+                        self.formats_panel.is_open = !self.formats_panel.is_open;
+                        ui.close_menu();
+                    }
+                    if ui.button(self.menu_text("Disassembly")).clicked() {
+                        self.disasm.is_open = !self.disasm.is_open;
+                        ui.close_menu();
+                    }
+                    if ui.button(self.menu_text("Hash")).clicked() {
+                        self.hash_panel.is_open = !self.hash_panel.is_open;
+                        ui.close_menu();
+                    }
+                    if ui.button(self.menu_text("Structure")).clicked() {
+                        self.structure.is_open = !self.structure.is_open;
+                        ui.close_menu();
+                    }
                     if ui.button(self.menu_text("Variables")).clicked() {
                         vars_clicked = true;
                         ui.close_menu();
                     }
+                    if ui
+                        .checkbox(&mut self.track_reads, "Track Reads")
+                        .on_hover_text("Wrap read to colour the bytes by variable")
+                        .changed()
+                    {
+                        self.reload_keeping_live_code();
+                        ui.close_menu();
+                    }
                     if ui.button(self.menu_text("Bytecode")).clicked() {
                         self.bytecode_open = !self.bytecode_open;
                         ui.close_menu();
@@ -1098,16 +2210,25 @@ impl TemplateApp {
                 });
         }
 
+        let mut stack_save = None;
         egui::SidePanel::left("hex_view")
-            .resizable(false)
+            .resizable(true)
+            .default_width(480.0)
             .show(ctx, |ui| {
-                let ncols = self.num_cols * 4 + 10;
-                let total_rows = self.num_rows;
                 let text_style = TextStyle::Monospace;
                 let font = text_style.resolve(ui.style());
                 let (glyph_width, row_height) =
                     ui.fonts(|f| (f.glyph_width(&font, '0'), f.row_height(&font)));
-                let size1 = Vec2::new(ncols as f32 * glyph_width, total_rows as f32 * row_height);
+                self.ui_input_tabs(ui);
+                self.hex_layout.ui(ui);
+                // one char of slack keeps the fitted rows from widening the panel
+                let width_chars = (ui.available_width() / glyph_width) as usize;
+                self.num_cols = self.hex_layout.num_cols(width_chars.saturating_sub(1));
+                let ncols = self.num_cols * 4 + hexview::ROW_EXTRA_CHARS;
+                // leave room for the header, the undo bar and the stack below the grid
+                let grid_height = ui.available_height() - 12.0 * row_height;
+                self.num_rows = ((grid_height / (row_height + 2.0)) as usize).max(1);
+                let size1 = Vec2::new(ncols as f32 * glyph_width, self.num_rows as f32 * row_height);
                 ui.set_min_width(size1.x);
 
                 let xgrid = ui.vertical(|ui| {
@@ -1115,8 +2236,13 @@ impl TemplateApp {
                     let mut from = self.view_pos;
                     let bs = self.current_bstr().seek(from).unwrap_or_default();
                     let mut it = bs.iter8();
-                    let visible_bits = self.num_rows * self.num_cols * 8;
+                    let visible_bits = self.num_rows * self.row_bits();
                     let to = bs.end().min(from + visible_bits);
+                    let bits = if self.hex_layout.bit_view.enabled {
+                        bitview::collect_bits(&bs, visible_bits)
+                    } else {
+                        Vec::new()
+                    };
                     ui.spacing_mut().item_spacing = vec2(0.0, 2.0);
                     ui.spacing_mut().interact_size = vec2(0.0, 0.0);
 
@@ -1135,51 +2261,159 @@ impl TemplateApp {
                         ui.colored_label(self.theme.comment, format!(" of {}", end));
                     });
 
+                    let ranges = reads::visible(&self.read_ranges, from..to);
+                    let mut clicked = None;
+                    let mut clicked_bit = None;
                     for _ in 0..self.num_rows {
                         let mut addr_text = self.hex_offset_str(from, bs.end());
                         if from >= to {
                             ui.colored_label(self.theme.comment, addr_text);
                             continue;
                         }
+                        if self.hex_layout.bit_view.enabled {
+                            ui.horizontal(|ui| {
+                                ui.colored_label(self.theme.comment, addr_text);
+                                let row = &bits[from - self.view_pos..];
+                                if let Some(pos) = self.ui_bit_row(ui, row, from, offset, &ranges) {
+                                    clicked_bit = Some(pos);
+                                }
+                            });
+                            from = (from + self.row_bits()).min(to);
+                            continue;
+                        }
                         ui.horizontal(|ui| {
                             addr_text.push_str(" ");
                             ui.colored_label(self.theme.comment, addr_text);
-                            let mut ascii = String::new();
-                            ascii.push_str("  ");
+                            let mut ascii = Vec::new();
                             for i in 0..self.num_cols {
                                 if let Some((val, n)) = it.next() {
-                                    let hex_data = RichText::new(format!(" {:02x}", val)).color(
-                                        if from < offset {
-                                            self.theme.code_frozen
-                                        } else {
-                                            self.theme.code
-                                        },
-                                    );
-                                    let hl = Label::new(hex_data).sense(Sense::hover());
-                                    ui.add(hl);
-                                    let c = xeh::bitstr_ext::byte_to_dump_char(val);
-                                    ascii.push(c);
+                                    let byte = from / 8;
+                                    let s = match self.hex_edit.nibble {
+                                        Some(hi) if self.hex_edit.cursor == Some(byte) => {
+                                            format!(" {:x}_", hi)
+                                        }
+                                        _ => format!(" {:02x}", val),
+                                    };
+                                    let range = ranges.iter().rev().find(|r| r.range.contains(&from));
+                                    let color = match range {
+                                        Some(r) => r.color,
+                                        None if from < offset => self.theme.code_frozen,
+                                        None => self.theme.code,
+                                    };
+                                    let hex_data = RichText::new(s).color(color);
+                                    let hex_data = self.hex_cell_text(hex_data, byte, false);
+                                    let hl = Label::new(hex_data).sense(Sense::click());
+                                    let mut resp = ui.add(hl);
+                                    if let Some(r) = range {
+                                        resp = resp.on_hover_text(match &r.name {
+                                            Some(name) => format!("{} = {}", name, r.value),
+                                            None => r.value.clone(),
+                                        });
+                                    }
+                                    if resp.clicked() {
+                                        clicked = Some((byte, false));
+                                    }
+                                    ascii.push((byte, val));
                                     from += n as usize;
                                 } else {
                                     let n = (self.num_cols - i) as usize;
                                     let mut pad = String::with_capacity(n * 3);
                                     for _ in 0..n {
                                         pad.push_str("   ");
-                                        ascii.push(' ');
                                     }
                                     ui.colored_label(self.theme.comment, pad);
                                     break;
                                 }
                             }
-                            ui.colored_label(self.theme.comment, ascii);
+                            ui.colored_label(self.theme.comment, "  ");
+                            for (byte, val) in ascii {
+                                let c = xeh::bitstr_ext::byte_to_dump_char(val);
+                                let text = RichText::new(c.to_string()).color(self.theme.comment);
+                                let text = self.hex_cell_text(text, byte, true);
+                                if ui.add(Label::new(text).sense(Sense::click())).clicked() {
+                                    clicked = Some((byte, true));
+                                }
+                            }
                         });
                     }
+                    if let Some((byte, ascii)) = clicked {
+                        if self.input().is_some() && self.view_pos.is_multiple_of(8) {
+                            if ui.input(|i| i.modifiers.shift) {
+                                self.hex_edit.extend(byte);
+                            } else {
+                                self.hex_edit.select(byte, ascii);
+                            }
+                        }
+                    }
+                    if let Some(pos) = clicked_bit {
+                        if self.input().is_some() {
+                            if ui.input(|i| i.modifiers.shift) {
+                                self.hex_edit.extend(pos / 8);
+                            } else {
+                                self.hex_edit.select(pos / 8, false);
+                                self.inspector.bit = pos % 8;
+                            }
+                        }
+                    }
                 });
 
                 let resp = xgrid.response.interact(egui::Sense::drag());
                 let v = resp.drag_delta();
                 self.move_view(v.y as isize);
 
+                if self.hex_edit.cursor.is_some() {
+                    let mut copy_format = None;
+                    let mut decompress_codec = None;
+                    ui.horizontal(|ui| {
+                        ui.menu_button(self.menu_text("📋Copy as"), |ui| {
+                            for format in CopyFormat::ALL {
+                                if ui.button(format.name()).clicked() {
+                                    copy_format = Some(format);
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+                        ui.menu_button(self.menu_text("Decompress as"), |ui| {
+                            for codec in Codec::ALL {
+                                if ui.button(codec.name()).clicked() {
+                                    decompress_codec = Some(codec);
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+                        if ui.button(self.menu_text("Generate read code")).clicked() {
+                            self.generate_read_code();
+                        }
+                    });
+                    if let Some(format) = copy_format {
+                        let text = binary::format_bytes(&self.selected_bytes(), format);
+                        ui.ctx().copy_text(text);
+                    }
+                    if let Some(codec) = decompress_codec {
+                        self.decompress_selection(codec);
+                    }
+                }
+
+                if self.hex_edit.can_undo() || self.hex_edit.can_redo() {
+                    ui.horizontal(|ui| {
+                        if ui
+                            .add_enabled(self.hex_edit.can_undo(), Button::new(self.menu_text("↶Undo")))
+                            .clicked()
+                        {
+                            self.patch_binary(|edit, bytes| edit.undo(bytes));
+                        }
+                        if ui
+                            .add_enabled(self.hex_edit.can_redo(), Button::new(self.menu_text("↷Redo")))
+                            .clicked()
+                        {
+                            self.patch_binary(|edit, bytes| edit.redo(bytes));
+                        }
+                        if ui.button(self.menu_text("💾Save As...")).clicked() {
+                            self.save_binary_as();
+                        }
+                    });
+                }
+
                 ui.colored_label(self.theme.comment, "Stack:");
 
                 egui::containers::ScrollArea::vertical().show(ui, |ui| {
@@ -1208,24 +2442,52 @@ impl TemplateApp {
                         ui.horizontal(|ui| {
                             ui.colored_label(self.theme.comment, format!("{:6}:", i));
                             ui.colored_label(color, s);
+                            if let Ok(bs) = val.bitstr() {
+                                if save_button(ui, bs).clicked() {
+                                    stack_save = Some((format!("stack{}.bin", i), bs.clone()));
+                                }
+                            }
                         });
                     }
                 });
             });
+        if let Some((name, bs)) = stack_save {
+            self.save_bitstr(&name, &bs);
+        }
+
+        if self.overview.is_open {
+            let bs = self.current_bstr().clone();
+            self.overview.set_input(&bs);
+            self.overview.step();
+            if !self.overview.is_complete() {
+                ctx.request_repaint();
+            }
+            let view = self.view_pos..self.view_pos + self.num_rows * self.row_bits();
+            egui::SidePanel::left("overview")
+                .resizable(false)
+                .exact_width(48.0)
+                .show(ctx, |ui| {
+                    if let Some(pos) = self.overview.ui(ui, &self.theme, view) {
+                        self.view_pos = pos;
+                    }
+                });
+        }
 
         egui::TopBottomPanel::bottom("status").show(ctx, |ui| {
             self.ui_mini_status(ui, true);
         });
 
-        let esc_pressed = ctx.input(|i| i.key_down(Key::Escape));
+        let esc_pressed = ctx.input(|i| i.key_down(Key::Escape)) && !self.completion.is_open;
         let mut live_has_focus = false;
+        let mut goto_word = None;
         egui::CentralPanel::default().show(ctx, |ui| {
+            self.ui_buffer_tabs(ui);
             egui::containers::ScrollArea::vertical()
                 .stick_to_bottom(true)
                 .show(ui, |ui| {
                     let old_spacing = ui.spacing_mut().item_spacing;
                     ui.spacing_mut().item_spacing = vec2(0.0, 0.0);
-                    for x in self.frozen_code.iter() {
+                    for (i, x) in self.frozen_code.iter().enumerate() {
                         match x {
                             FrozenStr::Log(s) | FrozenStr::TrialLog(s) => {
                                 ui.colored_label(self.theme.comment, s.trim_end().to_string());
@@ -1244,10 +2506,24 @@ impl TemplateApp {
                                         continue;
                                     }
                                 }
-                                ui.colored_label(self.theme.code_frozen, s.as_str());
+                                let highlight = self.frozen_highlight == Some(i);
+                                let mut text = RichText::new(s.as_str()).color(self.theme.code_frozen);
+                                if highlight {
+                                    text = text.background_color(self.theme.selection_background);
+                                }
+                                let (pos, galley, resp) =
+                                    Label::new(text).sense(Sense::click()).layout_in_ui(ui);
+                                ui.painter().galley(pos, galley.clone(), self.theme.code_frozen);
+                                if highlight && self.frozen_scroll {
+                                    ui.scroll_to_rect(resp.rect, Some(Align::Center));
+                                }
+                                if let Some(w) = self.ui_word_hover(resp, &galley, pos, s.as_str()) {
+                                    goto_word = Some(w);
+                                }
                             }
                         }
                     }
+                    self.frozen_scroll = false;
                     ui.spacing_mut().item_spacing = old_spacing;
                     let show_trial_error = self.is_trial()
                         && self.xs.last_error().is_some()
@@ -1277,6 +2553,7 @@ impl TemplateApp {
                             text,
                             errtok.as_ref(),
                             dbgtok.as_ref(),
+                            &self.lexicon,
                             &font_id,
                             wrap_width,
                             &self.theme.clone(),
@@ -1284,6 +2561,17 @@ impl TemplateApp {
                         ui.fonts(|f| f.layout_job(j))
                     };
                     let code_id = Id::new("live");
+                    if let Some(index) = self.live_cursor_jump.take() {
+                        let mut state = egui::text_edit::TextEditState::load(ctx, code_id)
+                            .unwrap_or_default();
+                        let ccursor = egui::text::CCursor::new(index);
+                        state.cursor.set_char_range(Some(egui::text::CCursorRange::one(ccursor)));
+                        state.store(ctx, code_id);
+                    }
+                    let mut accepted = None;
+                    if ctx.memory(|m| m.has_focus(code_id)) {
+                        accepted = self.completion.handle_keys(ctx);
+                    }
                     ui.style_mut().visuals.extreme_bg_color = self.theme.code_background;
                     let code = egui::TextEdit::multiline(&mut self.live_code)
                         .desired_rows(1)
@@ -1294,11 +2582,46 @@ impl TemplateApp {
                         .layouter(&mut layouter)
                         .show(ui);
                     ui.style_mut().visuals.extreme_bg_color = self.theme.border;
-                    let word = layouter::word_under_cursor(
-                        &self.live_code,
-                        code.cursor_range.map(|c| c.primary.ccursor.index),
-                    );
+                    let cursor_index = code.cursor_range.map(|c| c.primary.ccursor.index);
+                    let word = layouter::word_under_cursor(&self.live_code, cursor_index);
+                    if code.response.has_focus()
+                        && (code.response.changed() || ctx.input(hotkeys::completion_pressed))
+                    {
+                        match word.as_deref() {
+                            Some(w) if !w.is_empty() => {
+                                self.completion.update(w, self.completion_candidates())
+                            }
+                            _ => self.completion.close(),
+                        }
+                    }
+                    if let Some(c) = code.cursor_range {
+                        let pos = code.galley_pos
+                            + code.galley.pos_from_cursor(&c.primary).left_bottom().to_vec2();
+                        if let Some(w) = self.completion.show(ctx, pos, &self.theme) {
+                            accepted = Some(w);
+                        }
+                    }
+                    if !code.response.has_focus() && accepted.is_none() {
+                        self.completion.close();
+                    }
+                    let mut completed = false;
+                    if let Some(name) = accepted {
+                        if let Some(r) = layouter::word_range_under_cursor(&self.live_code, cursor_index) {
+                            let index = self.live_code[..r.start].chars().count() + name.chars().count();
+                            self.live_code.replace_range(r, &name);
+                            let mut state = code.state.clone();
+                            let ccursor = egui::text::CCursor::new(index);
+                            state.cursor.set_char_range(Some(egui::text::CCursorRange::one(ccursor)));
+                            state.store(ctx, code_id);
+                            code.response.request_focus();
+                            completed = true;
+                        }
+                    }
                     self.help.live_cursor = word;
+                    let resp = code.response.clone();
+                    if let Some(w) = self.ui_word_hover(resp, &code.galley, code.galley_pos, &self.live_code) {
+                        goto_word = Some(w);
+                    }
                     if esc_pressed {
                         code.response.surrender_focus();
                     } else if ctx.input(hotkeys::focus_on_code_pressed) || self.focus_on_code {
@@ -1307,13 +2630,21 @@ impl TemplateApp {
                     }
                     live_has_focus = code.response.has_focus();
 
-                    if code.response.changed() && !code_changed_remotely {
-                        if self.collab_system.is_connected() {
-                            self.collab_system.send(CollabMessage::Code { text: self.live_code.clone() });
-                        }
+                    if (code.response.changed() || completed)
+                        && !code_changed_remotely
+                        && self.collab_system.is_connected()
+                    {
+                        self.collab_system.send(CollabMessage::Code {
+                            buffer: self.buffers[self.active_buffer].name.clone(),
+                            text: self.live_code.clone(),
+                        });
                     }
                 });
 
+            if let Some(w) = goto_word.take() {
+                self.goto_definition(&w);
+            }
+
             // Sync Agents
             if self.collab_system.is_connected() {
                 let t = ctx.input(|i| i.time);
@@ -1340,8 +2671,49 @@ impl TemplateApp {
                 self.palette.is_open = !self.palette.is_open;
             }
 
-            if !live_has_focus && !self.help.is_open && !self.goto_open && !self.palette.is_open {
-                let n = hotkeys::scroll_view_pressed(ctx, self.num_cols as isize);
+            let hotkeys_enabled = !live_has_focus
+                && !self.help.is_open
+                && !self.goto_open
+                && !self.palette.is_open
+                && !ctx.wants_keyboard_input();
+            if hotkeys_enabled {
+                if ui.input(hotkeys::undo_pressed) {
+                    self.patch_binary(|edit, bytes| edit.undo(bytes));
+                }
+                if ui.input(hotkeys::redo_pressed) {
+                    self.patch_binary(|edit, bytes| edit.redo(bytes));
+                }
+            }
+            if self.compare.is_open && !self.palette.is_open {
+                if ui.input(hotkeys::next_diff_pressed) {
+                    self.compare.next_diff();
+                }
+                if ui.input(hotkeys::prev_diff_pressed) {
+                    self.compare.prev_diff();
+                }
+            }
+            if !self.palette.is_open {
+                let next = ui.input(hotkeys::find_next_pressed);
+                let prev = ui.input(hotkeys::find_prev_pressed);
+                let hit = if next {
+                    self.search.next(self.view_pos)
+                } else if prev {
+                    self.search.prev(self.view_pos)
+                } else {
+                    None
+                };
+                if let Some(pos) = hit {
+                    self.view_pos = pos;
+                }
+            }
+            if !self.hex_layout.bit_view.enabled && !self.view_pos.is_multiple_of(8) {
+                // the cells straddle two bytes, editing one of them would patch the other
+                self.hex_edit.deselect();
+            }
+            if hotkeys_enabled && self.hex_edit.cursor.is_some() {
+                self.hex_edit_keys(ctx);
+            } else if hotkeys_enabled {
+                let n = hotkeys::scroll_view_pressed(ctx, self.num_rows as isize);
                 if n != 0 {
                     self.move_view(n);
                 }
@@ -1378,10 +2750,16 @@ impl TemplateApp {
                 if ui.input(hotkeys::goto_pressed) {
                     goto_clicked = true;
                 }
+                if ui.input(hotkeys::find_pressed) {
+                    find_clicked = true;
+                }
             }
             if vars_clicked {
                 self.vars_open = true;
             }
+            if find_clicked {
+                self.search.is_open = !self.search.is_open;
+            }
             if goto_clicked {
                 self.goto_open = true;
                 self.goto_old_pos = Some(self.view_pos);
@@ -1389,7 +2767,19 @@ impl TemplateApp {
             if open_clicked {
                 self.open_file_dialog();
             }
-            if self.process_async_file_open() || self.process_file_drop(ctx) {
+            if save_binary_clicked {
+                self.save_binary_as();
+            }
+            if compare_clicked {
+                self.compare_future = Some(Self::pick_binary());
+            }
+            if self.process_async_file_open()
+                || self.process_async_compare_open()
+                || self.process_async_ksy_open()
+                || self.process_async_file_save()
+                || self.process_pending_saves()
+                || self.process_file_drop(ctx)
+            {
                 ctx.request_repaint();
             }
             if canvas_clicked {
@@ -1492,6 +2882,9 @@ impl TemplateApp {
                 self.live_code.clear();
                 self.last_dt = Some((t.elapsed().as_secs_f64(), ""));
             }
+            if next_clicked || rnext_clicked || run_clicked {
+                self.pending_saves.extend(output::take_pending());
+            }
             if next_clicked
                 || rnext_clicked
                 || run_clicked
@@ -1499,6 +2892,7 @@ impl TemplateApp {
                 || (self.is_trial() && has_some_code)
             {
                 self.debug_token = self.xs.location_from_current_ip();
+                self.lexicon.update(&self.xs, self.vars_boot_len);
                 if let Ok((w, h, buf)) = crate::canvas::copy_rgba(&mut self.xs) {
                     if self.canvas.is_empty() {
                         self.canvas_open = true;
@@ -1567,10 +2961,231 @@ impl TemplateApp {
             if let Some(n) = self.rlog_size() {
                 write!(s, " RLOG {}", n).unwrap();
             }
+            if let Some(sel) = self.selection_status() {
+                write!(s, " {}", sel).unwrap();
+            }
             ui.colored_label(self.theme.comment, s);
         }
     }
 
+    /// Show word documentation on hover, return the word if it was Ctrl+clicked.
+    fn ui_word_hover(&self, resp: Response, galley: &Galley, galley_pos: Pos2, text: &str) -> Option<String> {
+        let pos = resp.hover_pos()?;
+        let cursor = galley.cursor_from_pos(pos - galley_pos);
+        let word = layouter::word_under_cursor(text, Some(cursor.ccursor.index))?;
+        let help = self.help.words.iter().find(|(w, _)| w.as_str() == word).map(|x| &x.1);
+        let is_word = self.lexicon.words.contains(&word);
+        let var = self.xs.var_list().into_iter().rev().find(|(name, _)| name.as_str() == word);
+        if help.is_none() && !is_word && var.is_none() {
+            return None;
+        }
+        let clicked = resp.clicked() && resp.ctx.input(|i| i.modifiers.command);
+        resp.on_hover_ui_at_pointer(|ui| {
+            ui.horizontal(|ui| {
+                ui.monospace(RichText::new(word.as_str()).color(self.theme.selection));
+                if let Some(t) = help.and_then(|h| h.get(&STACK_TAG)).and_then(|t| t.str().ok()) {
+                    ui.colored_label(self.theme.comment, format!(" # ( {} ) ", t));
+                }
+            });
+            if let Some(Ok(s)) = help.and_then(|h| h.get(&HELPTEXT_TAG)).map(|x| x.str()) {
+                ui.label(s);
+            }
+            if let Some(Ok(s)) = help.and_then(|h| h.get(&EXAMPLE_TAG)).map(|x| x.str()) {
+                ui.separator();
+                ui.monospace(
+                    RichText::new(s)
+                        .color(self.theme.code_frozen)
+                        .background_color(self.theme.code_background),
+                );
+            }
+            if let Some((_, val)) = &var {
+                ui.colored_label(self.theme.code_frozen, val.format_safe());
+            }
+            if is_word {
+                ui.colored_label(self.theme.comment, "Ctrl+Click to go to definition");
+            }
+        });
+        if clicked && is_word {
+            Some(word)
+        } else {
+            None
+        }
+    }
+
+    /// Jump to the latest frozen line or buffer where the word was defined.
+    fn goto_definition(&mut self, name: &str) {
+        let frozen = self.frozen_code.iter().rposition(|x| match x {
+            FrozenStr::Code(s) => layouter::find_definition(s, name).is_some(),
+            _ => false,
+        });
+        if let Some(i) = frozen {
+            self.frozen_highlight = Some(i);
+            self.frozen_scroll = true;
+            return;
+        }
+        for i in 0..self.buffers.len() {
+            let text = if i == self.active_buffer {
+                &self.live_code
+            } else {
+                &self.buffers[i].code
+            };
+            if let Some(pos) = layouter::find_definition(text, name) {
+                let index = text[..pos].chars().count();
+                self.switch_buffer(i);
+                self.live_cursor_jump = Some(index);
+                self.focus_on_code = true;
+                return;
+            }
+        }
+    }
+
+    fn completion_candidates(&self) -> Vec<Candidate> {
+        let mut seen = HashSet::new();
+        let mut res = Vec::new();
+        let mut add = |name: &str, kind, stack: &str, text: &str| {
+            if seen.insert(name.to_string()) {
+                res.push(Candidate {
+                    name: name.to_string(),
+                    kind,
+                    stack: stack.to_string(),
+                    text: text.to_string(),
+                });
+            }
+        };
+        for name in &self.lexicon.vars {
+            add(name, "var", "-- val", "");
+        }
+        for name in &self.lexicon.words {
+            add(name, "word", "", "");
+        }
+        for (word, help) in &self.help.words {
+            let stack = help.get(&STACK_TAG).and_then(|s| s.str().ok()).unwrap_or("");
+            let text = help.get(&HELPTEXT_TAG).and_then(|s| s.str().ok()).unwrap_or("");
+            add(word.as_str(), "builtin", stack, text);
+        }
+        for name in &self.lexicon.builtins {
+            add(name, "builtin", "", "");
+        }
+        res
+    }
+
+    fn ui_buffer_tabs(&mut self, ui: &mut Ui) {
+        let mut switch_to = None;
+        let mut close = None;
+        let mut rename = None;
+        let mut add_clicked = false;
+        ui.horizontal_wrapped(|ui| {
+            for (i, buf) in self.buffers.iter().enumerate() {
+                let active = i == self.active_buffer;
+                let text = RichText::new(buf.name.as_str()).monospace().color(if active {
+                    self.theme.selection
+                } else {
+                    self.theme.comment
+                });
+                let resp = ui.selectable_label(active, text);
+                if resp.clicked() {
+                    switch_to = Some(i);
+                }
+                if resp.secondary_clicked() {
+                    self.buffer_rename = buf.name.clone();
+                }
+                resp.context_menu(|ui| {
+                    ui.horizontal(|ui| {
+                        ui.text_edit_singleline(&mut self.buffer_rename);
+                        if ui.button("Rename").clicked() {
+                            rename = Some(i);
+                            ui.close_menu();
+                        }
+                    });
+                    if ui
+                        .add_enabled(self.buffers.len() > 1, Button::new("Close"))
+                        .clicked()
+                    {
+                        close = Some(i);
+                        ui.close_menu();
+                    }
+                });
+                ui.separator();
+            }
+            add_clicked = ui.button(self.menu_text("➕")).clicked();
+        });
+        if let Some(i) = switch_to {
+            self.switch_buffer(i);
+        }
+        if let Some(i) = rename {
+            let name = self.buffer_rename.trim();
+            if !name.is_empty() && !self.buffers.iter().any(|b| b.name == name) {
+                self.buffers[i].name = name.to_string();
+            }
+        }
+        if let Some(i) = close {
+            self.close_buffer(i);
+        }
+        if add_clicked {
+            self.new_buffer("scratch", String::new());
+            self.focus_on_code = true;
+        }
+    }
+
+    fn ui_input_tabs(&mut self, ui: &mut Ui) {
+        let mut select = None;
+        let mut close = None;
+        let mut open_clicked = false;
+        ui.horizontal_wrapped(|ui| {
+            for (i, input) in self.inputs.iter().enumerate() {
+                let active = i == self.active_input;
+                let text = RichText::new(input.name.as_str()).monospace().color(if active {
+                    self.theme.selection
+                } else {
+                    self.theme.comment
+                });
+                let resp = ui.selectable_label(active, text);
+                if resp.clicked() {
+                    select = Some(i);
+                }
+                resp.on_hover_text(format!("\"{}\" input/select", input.name))
+                    .context_menu(|ui| {
+                        if ui.button("Close").clicked() {
+                            close = Some(i);
+                            ui.close_menu();
+                        }
+                    });
+                ui.separator();
+            }
+            open_clicked = ui.button(self.menu_text("➕")).on_hover_text("Open binary").clicked();
+        });
+        let mut back = None;
+        let crumbs = self.input_breadcrumbs();
+        if let Some(input) = self.active().filter(|_| !crumbs.is_empty()) {
+            ui.horizontal_wrapped(|ui| {
+                for (i, p) in &crumbs {
+                    let name = &self.inputs[*i].name;
+                    if ui.link(self.menu_text(name)).clicked() {
+                        back = Some((*i, p.range.clone()));
+                    }
+                    let text = format!("› {} @ {} ›", p.codec, self.hex_offset_str(p.range.start, 0));
+                    ui.colored_label(self.theme.comment, text);
+                }
+                ui.colored_label(self.theme.selection, input.name.as_str());
+            });
+        }
+
+        if let Some(i) = select {
+            self.select_input(i);
+        }
+        // back to the compressed region the child was opened from
+        if let Some((i, range)) = back {
+            self.select_input(i);
+            self.select_bits(range);
+        }
+        if let Some(i) = close {
+            self.close_input(i);
+        }
+        if open_clicked {
+            self.open_file_dialog();
+        }
+    }
+
     fn ui_error_highlight(&self, ui: &mut Ui, loc: &TokenLocation, err: &Xerr) {
         let (a, b, c) = split_highlight(loc);
         ui.horizontal(|ui| {
@@ -1599,66 +3214,111 @@ impl TemplateApp {
     }
 
     fn menu_examples(&mut self, ui: &mut Ui) {
-        if ui.button("C String").clicked() {
-            self.example_request = Some((
-                include_str!("../assets/examples/cstring.xeh"),
-                include_bytes!("../assets/examples/cstring.bin"),
-            ));
-            ui.close_menu();
-        }
-        if ui.button("Doom Fire").clicked() {
-            self.example_request = Some((include_str!("../assets/examples/doom-fire.xeh"), &[]));
-            ui.close_menu();
-        }
-        if ui.button("Gameboy Tile 2BPP").clicked() {
-            self.example_request = Some((
-                include_str!("../assets/examples/gb-tile-2bpp.xeh"),
-                include_bytes!("../assets/examples/gb-tile-2bpp.bin"),
-            ));
-            ui.close_menu();
-        }
-        if ui.button("iNES ROM").clicked() {
-            self.example_request = Some((
-                include_str!("../assets/examples/ines.xeh"),
-                include_bytes!("../assets/examples/ines.bin"),
-            ));
-            ui.close_menu();
-        }
-        if ui.button("Quake1Pak").clicked() {
-            self.example_request = Some((
-                include_str!("../assets/examples/quake-pak.xeh"),
-                include_bytes!("../assets/examples/quake-pak.bin"),
-            ));
-            ui.close_menu();
-        }
-        if ui.button("Quake1Pak Build").clicked() {
-            self.example_request =
-                Some((include_str!("../assets/examples/quake-pak-build.xeh"), &[]));
-            ui.close_menu();
-        }
-        if ui.button("VLQ Integer").clicked() {
-            self.example_request = Some((
-                include_str!("../assets/examples/vint.xeh"),
-                include_bytes!("../assets/examples/vint.bin"),
-            ));
-            ui.close_menu();
-        }
-        // if ui.button("6502 instructions").clicked() {
-        //     self.example_request = Some(include_str!("docs/ex/6502.xeh"),
-        //                                    include_bytes!("docs/ex/6502.bin"));
-        //     ui.close_menu();
-        // }
+        for (i, f) in self.formats.formats.iter().enumerate() {
+            if f.example.is_some() && ui.button(f.name.as_str()).clicked() {
+                self.example_request = Some(i);
+                ui.close_menu();
+            }
+        }
+    }
+
+    fn pick_binary() -> Pin<BoxFuture> {
+        Box::pin(async {
+            let file = rfd::AsyncFileDialog::new().pick_file().await?;
+            #[cfg(not(target_arch = "wasm32"))]
+            return Some(PickedFile::Path(file.path().to_path_buf()));
+            #[cfg(target_arch = "wasm32")]
+            return Some(PickedFile::Data(file.file_name(), file.read().await));
+        })
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn open_path(&mut self, path: &std::path::Path) {
+        match inputs::load_path(path) {
+            Ok(s) => {
+                let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+                let path = path.to_string_lossy().to_string();
+                self.add_input(Input::new(&name, s, Some(path)));
+                self.offer_formats();
+            }
+            Err(e) => {
+                let msg = format!("Failed to open {:?}: {}", path, e);
+                self.frozen_code.push(FrozenStr::Log(msg));
+            }
+        }
     }
 
     fn open_file_dialog(&mut self) {
-        self.bin_future = Some(Box::pin(async {
-            let res = rfd::AsyncFileDialog::new().pick_file().await;
-            if let Some(file) = res {
-                Some(file.read().await)
-            } else {
-                None
+        self.bin_future = Some(Self::pick_binary());
+    }
+
+    fn process_async_compare_open(&mut self) -> bool {
+        if let Some(future) = self.compare_future.as_mut() {
+            let waker = Arc::new(MyWaker()).into();
+            let context = &mut Context::from_waker(&waker);
+            match Pin::new(future).poll(context) {
+                Poll::Pending => (),
+                Poll::Ready(res) => {
+                    self.compare_future.take();
+                    match res {
+                        #[cfg(target_arch = "wasm32")]
+                        Some(PickedFile::Data(name, data))
+                            if data.len() > binary::MAX_COPY_BYTES =>
+                        {
+                            let msg = format!("{} is too large to compare", name);
+                            self.frozen_code.push(FrozenStr::Log(msg));
+                        }
+                        #[cfg(target_arch = "wasm32")]
+                        Some(PickedFile::Data(name, data)) => self.compare.set_other(&name, data),
+                        #[cfg(not(target_arch = "wasm32"))]
+                        Some(PickedFile::Path(path)) => match binary::read_file_limited(&path) {
+                            Ok(data) => {
+                                let name = path.file_name().map(|n| n.to_string_lossy());
+                                self.compare.set_other(&name.unwrap_or_default(), data);
+                            }
+                            Err(e) => {
+                                let msg = format!("Failed to open {:?}: {}", path, e);
+                                self.frozen_code.push(FrozenStr::Log(msg));
+                            }
+                        },
+                        None => (),
+                    }
+                }
+            }
+        }
+        self.compare_future.is_some()
+    }
+
+    fn process_async_ksy_open(&mut self) -> bool {
+        if let Some(future) = self.ksy_future.as_mut() {
+            let waker = Arc::new(MyWaker()).into();
+            let context = &mut Context::from_waker(&waker);
+            match Pin::new(future).poll(context) {
+                Poll::Pending => (),
+                Poll::Ready(res) => {
+                    self.ksy_future.take();
+                    match res {
+                        #[cfg(target_arch = "wasm32")]
+                        Some(PickedFile::Data(name, data)) => {
+                            self.import_ksy(&name, &String::from_utf8_lossy(&data))
+                        }
+                        #[cfg(not(target_arch = "wasm32"))]
+                        Some(PickedFile::Path(path)) => {
+                            let name = path.file_name().map(|n| n.to_string_lossy().to_string());
+                            match std::fs::read_to_string(&path) {
+                                Ok(text) => self.import_ksy(&name.unwrap_or_default(), &text),
+                                Err(e) => {
+                                    let msg = format!("Failed to open {:?}: {}", path, e);
+                                    self.frozen_code.push(FrozenStr::Log(msg));
+                                }
+                            }
+                        }
+                        None => (),
+                    }
+                }
             }
-        }));
+        }
+        self.ksy_future.is_some()
     }
 
     fn process_async_file_open(&mut self) -> bool {
@@ -1670,21 +3330,78 @@ impl TemplateApp {
                 Poll::Ready(None) => {
                     self.bin_future.take();
                 }
-                Poll::Ready(Some(data)) => {
-                    let s = Xbitstr::from(data);
+                Poll::Ready(Some(file)) => {
                     self.bin_future.take();
-                    self.binary_dropped(s);
+                    match file {
+                        #[cfg(target_arch = "wasm32")]
+                        PickedFile::Data(name, data) => self.binary_dropped(&name, Xbitstr::from(data)),
+                        #[cfg(not(target_arch = "wasm32"))]
+                        PickedFile::Path(path) => self.open_path(&path),
+                    }
                 }
             }
         }
         return self.bin_future.is_some();
     }
 
+    fn save_bitstr(&mut self, name: &str, bs: &Xbitstr) {
+        self.pending_saves.push(output::SaveRequest {
+            name: name.to_string(),
+            data: output::padded_bytes(bs),
+        });
+    }
+
+    fn process_pending_saves(&mut self) -> bool {
+        if self.save_future.is_none() && !self.pending_saves.is_empty() {
+            let req = self.pending_saves.remove(0);
+            self.save_future = binary::save_binary(&req.name, req.data);
+        }
+        !self.pending_saves.is_empty()
+    }
+
+    fn process_async_file_save(&mut self) -> bool {
+        if let Some(future) = self.save_future.as_mut() {
+            let waker = Arc::new(MyWaker()).into();
+            let context = &mut Context::from_waker(&waker);
+            match future.as_mut().poll(context) {
+                Poll::Pending => (),
+                Poll::Ready(res) => {
+                    self.save_future.take();
+                    if let Some(msg) = res {
+                        self.frozen_code.push(FrozenStr::Log(msg));
+                    }
+                }
+            }
+        }
+        self.save_future.is_some()
+    }
+
     fn process_file_drop(&mut self, ctx: &egui::Context) -> bool {
         if let Some(d) = ctx.input(|i| i.raw.dropped_files.first().cloned()) {
+            let is_ksy = |name: &str| name.to_ascii_lowercase().ends_with(".ksy");
             if let Some(data) = &d.bytes {
+                if is_ksy(&d.name) {
+                    self.import_ksy(&d.name, &String::from_utf8_lossy(data));
+                    return true;
+                }
                 let s = Xbitstr::from(data.as_ref().to_owned());
-                self.binary_dropped(s);
+                self.binary_dropped(&d.name, s);
+                return true;
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some(path) = &d.path {
+                if is_ksy(&path.to_string_lossy()) {
+                    let name = path.file_name().map(|n| n.to_string_lossy().to_string());
+                    match std::fs::read_to_string(path) {
+                        Ok(text) => self.import_ksy(&name.unwrap_or_default(), &text),
+                        Err(e) => {
+                            let msg = format!("Failed to open {:?}: {}", path, e);
+                            self.frozen_code.push(FrozenStr::Log(msg));
+                        }
+                    }
+                    return true;
+                }
+                self.open_path(path);
                 return true;
             }
         }
@@ -1703,6 +3420,16 @@ impl Wake for MyWaker {
     fn wake(self: Arc<Self>) {}
 }
 
+/// Saves the bitstr, the tooltip tells when the last byte gets padded.
+fn save_button(ui: &mut Ui, bs: &Xbitstr) -> egui::Response {
+    let hint = if output::is_byte_aligned(bs) {
+        "Save to file...".to_string()
+    } else {
+        format!("Save to file, {} bits padded with zeros...", bs.end() - bs.start())
+    };
+    ui.small_button("💾").on_hover_text(hint)
+}
+
 fn split_highlight(loc: &TokenLocation) -> (String, String, String) {
     let line = &loc.whole_line;
     let start = loc.col;
@@ -1724,14 +3451,12 @@ impl eframe::App for TemplateApp {
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
         eframe::set_value(storage, eframe::APP_KEY, &self.theme);
         eframe::set_value(storage, "interval", &self.interval_word);
+        eframe::set_value(storage, "hex_layout", &self.hex_layout);
+        eframe::set_value(storage, "track_reads", &self.track_reads);
+        eframe::set_value(storage, "formats_dir", &self.formats_panel.user_dir);
 
         // Update current workspace before saving
-        let ws = Workspace {
-            name: self.current_workspace.clone(),
-            code: self.live_code.clone(),
-            tasks: self.agent_system.tasks.clone(),
-        };
-        self.workspaces.insert(self.current_workspace.clone(), ws);
+        self.save_workspace();
 
         eframe::set_value(storage, "workspaces", &self.workspaces);
         eframe::set_value(storage, "current_workspace", &self.current_workspace);
@@ -1742,10 +3467,13 @@ impl eframe::App for TemplateApp {
     /// Put your widgets into a `SidePanel`, `TopPanel`, `CentralPanel`, `Window` or `Area`.
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         crate::style::tune(ctx, &self.theme);
-        if let Some((code, data)) = self.example_request.take() {
+        if let Some(i) = self.example_request.take() {
+            let f = &self.formats.formats[i];
+            let code = f.source.to_string();
+            let data = Xbitstr::from(f.example.unwrap_or_default());
             self.frozen_code.clear();
-            self.binary_dropped(Xbitstr::from(data));
-            self.live_code = code.to_string();
+            self.add_input(Input::new("example.bin", data, None));
+            self.live_code = code;
         }
         self.editor(ctx);
     }
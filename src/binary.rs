@@ -0,0 +1,299 @@
+use std::future::Future;
+use std::ops::Range;
+use std::pin::Pin;
+use xeh::prelude::*;
+
+/// Patching copies the input into memory, don't do that to mapped disk images.
+pub const MAX_PATCH_BYTES: usize = 256 * 1024 * 1024;
+/// Whole input copies (find, compare, hashes, export, save) are refused above this size.
+pub const MAX_COPY_BYTES: usize = 256 * 1024 * 1024;
+
+pub type SaveFuture = Pin<Box<dyn Future<Output = Option<String>>>>;
+
+/// Bytes of the bitstr, the last partial byte is included as is.
+pub fn to_bytes(bs: &Xbitstr) -> Vec<u8> {
+    bs.iter8().map(|(b, _)| b).collect()
+}
+
+/// Bytes of the bitstr unless it's too large to copy into memory, e.g. a mapped disk image.
+pub fn to_bytes_limited(bs: &Xbitstr) -> Result<Vec<u8>, String> {
+    let len = (bs.end() - bs.start()).div_ceil(8);
+    if len > MAX_COPY_BYTES {
+        return Err(format!("{} bytes is too large, limit {} bytes", len, MAX_COPY_BYTES));
+    }
+    Ok(to_bytes(bs))
+}
+
+/// Up to `len` bytes starting at the byte offset, positions are those of the whole input.
+pub fn bytes_at(bs: &Xbitstr, offset: usize, len: usize) -> Vec<u8> {
+    match bs.seek(offset * 8) {
+        Some(bs) => bs.iter8().take(len).map(|(b, _)| b).collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Contents of the file unless it's larger than a whole input copy may be.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_file_limited(path: &std::path::Path) -> Result<Vec<u8>, String> {
+    let len = std::fs::metadata(path).map_err(|e| e.to_string())?.len();
+    if len > MAX_COPY_BYTES as u64 {
+        return Err(format!("{} bytes is too large, limit {} bytes", len, MAX_COPY_BYTES));
+    }
+    std::fs::read(path).map_err(|e| e.to_string())
+}
+
+#[derive(Clone, Copy)]
+struct Patch {
+    offset: usize,
+    old: u8,
+    new: u8,
+}
+
+/// Byte editing state of the hex view.
+#[derive(Default)]
+pub struct HexEditor {
+    /// Byte offset of the edit cursor.
+    pub cursor: Option<usize>,
+    /// Typing goes into the ASCII column instead of the hex one.
+    pub ascii: bool,
+    /// High nibble typed so far.
+    pub nibble: Option<u8>,
+    /// Fixed end of the selected range, the cursor is the moving one.
+    pub anchor: Option<usize>,
+    undo: Vec<Patch>,
+    redo: Vec<Patch>,
+}
+
+impl HexEditor {
+    pub fn select(&mut self, offset: usize, ascii: bool) {
+        self.cursor = Some(offset);
+        self.ascii = ascii;
+        self.nibble = None;
+        self.anchor = None;
+    }
+
+    /// Move the cursor keeping the other end of the selection in place.
+    pub fn extend(&mut self, offset: usize) {
+        if self.anchor.is_none() {
+            self.anchor = self.cursor.or(Some(offset));
+        }
+        self.cursor = Some(offset);
+        self.nibble = None;
+    }
+
+    pub fn deselect(&mut self) {
+        self.cursor = None;
+        self.nibble = None;
+        self.anchor = None;
+    }
+
+    /// Selected bytes, just the one under the cursor if there is no range.
+    pub fn selection(&self) -> Option<Range<usize>> {
+        let cursor = self.cursor?;
+        let anchor = self.anchor.unwrap_or(cursor);
+        Some(cursor.min(anchor)..cursor.max(anchor) + 1)
+    }
+
+    pub fn move_cursor(&mut self, delta: isize, len: usize) {
+        if let Some(pos) = self.cursor {
+            let pos = (pos as isize + delta).max(0) as usize;
+            self.cursor = Some(pos.min(len.saturating_sub(1)));
+            self.nibble = None;
+        }
+    }
+
+    pub fn is_modified(&self, offset: usize) -> bool {
+        self.undo.iter().any(|p| p.offset == offset)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Forget patches, the binary they were made against is gone.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Apply typed text at the cursor, return true if any byte has changed.
+    pub fn input(&mut self, text: &str, bytes: &mut [u8]) -> bool {
+        let mut changed = false;
+        for c in text.chars() {
+            let Some(pos) = self.cursor.filter(|&pos| pos < bytes.len()) else {
+                break;
+            };
+            let val = if self.ascii {
+                if !c.is_ascii() || c.is_ascii_control() {
+                    continue;
+                }
+                c as u8
+            } else {
+                let Some(digit) = c.to_digit(16) else {
+                    continue;
+                };
+                match self.nibble.take() {
+                    None => {
+                        self.nibble = Some(digit as u8);
+                        continue;
+                    }
+                    Some(hi) => (hi << 4) | digit as u8,
+                }
+            };
+            if bytes[pos] != val {
+                self.undo.push(Patch {
+                    offset: pos,
+                    old: bytes[pos],
+                    new: val,
+                });
+                self.redo.clear();
+                bytes[pos] = val;
+                changed = true;
+            }
+            self.cursor = Some((pos + 1).min(bytes.len() - 1));
+        }
+        changed
+    }
+
+    pub fn undo(&mut self, bytes: &mut [u8]) -> bool {
+        match self.undo.pop() {
+            Some(p) if p.offset < bytes.len() => {
+                bytes[p.offset] = p.old;
+                self.cursor = Some(p.offset);
+                self.redo.push(p);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn redo(&mut self, bytes: &mut [u8]) -> bool {
+        match self.redo.pop() {
+            Some(p) if p.offset < bytes.len() => {
+                bytes[p.offset] = p.new;
+                self.cursor = Some(p.offset);
+                self.undo.push(p);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CopyFormat {
+    Hex,
+    CArray,
+    RustSlice,
+    Base64,
+    Bitstr,
+}
+
+impl CopyFormat {
+    pub const ALL: [CopyFormat; 5] = [
+        CopyFormat::Hex,
+        CopyFormat::CArray,
+        CopyFormat::RustSlice,
+        CopyFormat::Base64,
+        CopyFormat::Bitstr,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CopyFormat::Hex => "Hex",
+            CopyFormat::CArray => "C Array",
+            CopyFormat::RustSlice => "Rust &[u8]",
+            CopyFormat::Base64 => "Base64",
+            CopyFormat::Bitstr => "xeh Bitstr",
+        }
+    }
+}
+
+/// Run a core xeh encoding word on a single argument.
+fn xeh_encoding(word: &str, arg: Cell) -> Xresult1<Cell> {
+    let mut xs = Xstate::core()?;
+    xs.push_data(arg)?;
+    xs.eval(word)?;
+    xs.pop_data()
+}
+
+pub fn base64(data: &[u8]) -> String {
+    let res = xeh_encoding("base64", Cell::from(Xbitstr::from(data.to_vec())));
+    res.ok()
+        .and_then(|c| c.str().ok().map(|s| s.to_string()))
+        .unwrap_or_default()
+}
+
+pub fn from_base64(text: &str) -> Option<Vec<u8>> {
+    let res = xeh_encoding("base64>", Cell::from(text)).ok()?;
+    Some(to_bytes(res.bitstr().ok()?))
+}
+
+pub fn format_bytes(data: &[u8], format: CopyFormat) -> String {
+    let hex = |sep: &str, prefix: &str| {
+        data.iter()
+            .map(|b| format!("{}{:02x}", prefix, b))
+            .collect::<Vec<_>>()
+            .join(sep)
+    };
+    match format {
+        CopyFormat::Hex => hex(" ", ""),
+        CopyFormat::CArray => format!(
+            "unsigned char data[{}] = {{ {} }};",
+            data.len(),
+            hex(", ", "0x")
+        ),
+        CopyFormat::RustSlice => format!("&[{}]", hex(", ", "0x")),
+        CopyFormat::Base64 => base64(data),
+        CopyFormat::Bitstr => format!("|{}|", hex(" ", "").to_uppercase()),
+    }
+}
+
+/// Ask where to save the data, on the web it's downloaded right away.
+pub fn save_binary(name: &str, data: Vec<u8>) -> Option<SaveFuture> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let dialog = rfd::AsyncFileDialog::new().set_file_name(name);
+        Some(Box::pin(async move {
+            let file = dialog.save_file().await?;
+            let res = match std::fs::write(file.path(), &data) {
+                Ok(()) => format!("Saved {} bytes to {:?}", data.len(), file.path()),
+                Err(e) => format!("Failed to save {:?}: {}", file.path(), e),
+            };
+            Some(res)
+        }))
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        download(name, &data);
+        None
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn download(name: &str, data: &[u8]) {
+    use eframe::wasm_bindgen::JsCast as _;
+    let Some(document) = web_sys::window().and_then(|w| w.document()) else {
+        return;
+    };
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(data));
+    let opts = web_sys::BlobPropertyBag::new();
+    opts.set_type("application/octet-stream");
+    let Ok(blob) = web_sys::Blob::new_with_u8_array_sequence_and_options(&parts, &opts) else {
+        return;
+    };
+    let Ok(url) = web_sys::Url::create_object_url_with_blob(&blob) else {
+        return;
+    };
+    if let Ok(a) = document.create_element("a") {
+        if let Ok(a) = a.dyn_into::<web_sys::HtmlAnchorElement>() {
+            a.set_href(&url);
+            a.set_download(name);
+            a.click();
+        }
+    }
+    let _ = web_sys::Url::revoke_object_url(&url);
+}
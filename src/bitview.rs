@@ -0,0 +1,82 @@
+use egui::{DragValue, Ui};
+use xeh::prelude::*;
+
+/// Binary display settings of the hex panel.
+#[cfg_attr(feature = "persistence", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "persistence", serde(default))]
+#[derive(Clone, Copy, PartialEq)]
+pub struct BitView {
+    pub enabled: bool,
+    /// Number of bits printed together, 1 to 32.
+    pub group: usize,
+    /// Print the first bit of a group on the right.
+    pub lsb_first: bool,
+}
+
+impl Default for BitView {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            group: 8,
+            lsb_first: false,
+        }
+    }
+}
+
+impl BitView {
+    /// Groups fitting into the width of a hex row of the given number of columns.
+    pub fn groups_per_row(&self, num_cols: usize) -> usize {
+        (num_cols * 4 / (self.group + 1)).max(1)
+    }
+
+    pub fn row_bits(&self, num_cols: usize) -> usize {
+        self.groups_per_row(num_cols) * self.group
+    }
+
+    /// Indices of the group bits in the display order.
+    pub fn display_order(&self, n: usize) -> Vec<usize> {
+        if self.lsb_first {
+            (0..n).rev().collect()
+        } else {
+            (0..n).collect()
+        }
+    }
+
+    /// Numeric value of the group bits in the stream order.
+    pub fn value(&self, bits: &[bool]) -> u64 {
+        let fold = |acc: u64, (i, &b): (usize, &bool)| {
+            if !b {
+                acc
+            } else if self.lsb_first {
+                acc | 1 << i
+            } else {
+                acc | 1 << (bits.len() - 1 - i)
+            }
+        };
+        bits.iter().enumerate().fold(0, fold)
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui) {
+        ui.selectable_value(&mut self.enabled, false, "Hex");
+        ui.selectable_value(&mut self.enabled, true, "Bits");
+        if self.enabled {
+            ui.add(DragValue::new(&mut self.group).range(1..=32).suffix(" bits"));
+            ui.selectable_value(&mut self.lsb_first, false, "MSB");
+            ui.selectable_value(&mut self.lsb_first, true, "LSB");
+        }
+    }
+}
+
+/// First bits of the bitstr in the stream order.
+pub fn collect_bits(bs: &Xbitstr, count: usize) -> Vec<bool> {
+    let mut bits = Vec::with_capacity(count + 8);
+    for (val, n) in bs.iter8() {
+        if bits.len() >= count {
+            break;
+        }
+        let n = n as usize;
+        bits.extend((0..n).map(|k| (val >> (n - 1 - k)) & 1 != 0));
+    }
+    bits.truncate(count);
+    bits
+}
@@ -0,0 +1,149 @@
+use crate::style::Theme;
+use egui::{Color32, RichText, ScrollArea, TextEdit, Ui};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use xeh::prelude::*;
+
+/// Inputs above this size are hashed from samples, they may be mapped files.
+const FULL_HASH_LIMIT: usize = 64 * 1024 * 1024;
+const SAMPLES: usize = 64;
+const SAMPLE_SIZE: usize = 64 * 1024;
+
+/// Bookmarks of every binary seen in a workspace, keyed by the content hash.
+pub type BookmarkMap = HashMap<String, Vec<Bookmark>>;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub name: String,
+    /// Bit offset of the first byte.
+    pub start: usize,
+    /// Length in bits.
+    pub len: usize,
+    pub color: [u8; 3],
+    #[serde(default)]
+    pub comment: String,
+}
+
+impl Bookmark {
+    pub fn color32(&self) -> Color32 {
+        let [r, g, b] = self.color;
+        Color32::from_rgb(r, g, b)
+    }
+
+    pub fn contains(&self, pos: usize) -> bool {
+        pos >= self.start && pos < self.start + self.len.max(1)
+    }
+}
+
+/// FNV-1a of the binary, bookmarks stay with the bytes whatever the file is called.
+fn content_hash(data: impl Iterator<Item = u8>) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in data {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// Key of the input bookmarks, the length and samples spread over big inputs.
+pub fn input_hash(bs: &Xbitstr) -> String {
+    let len = (bs.end() - bs.start()).div_ceil(8);
+    if len <= FULL_HASH_LIMIT {
+        return content_hash(bs.iter8().map(|(b, _)| b));
+    }
+    let mut data = len.to_le_bytes().to_vec();
+    let step = len / SAMPLES;
+    for i in 0..SAMPLES {
+        if let Some(s) = bs.seek(bs.start() + i * step * 8) {
+            data.extend(s.iter8().take(SAMPLE_SIZE).map(|(b, _)| b));
+        }
+    }
+    content_hash(data.into_iter())
+}
+
+/// Bit offset of the bookmark named by the whole text or replace bookmark names
+/// found among the words with their offsets.
+pub fn resolve(text: &str, bookmarks: &[Bookmark]) -> String {
+    let text = text.trim();
+    if let Some(b) = bookmarks.iter().find(|b| b.name == text) {
+        return b.start.to_string();
+    }
+    text.split_whitespace()
+        .map(|w| match bookmarks.iter().find(|b| b.name == w) {
+            Some(b) => b.start.to_string(),
+            None => w.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[derive(Default)]
+pub struct BookmarksPanel {
+    pub is_open: bool,
+    selected: Option<usize>,
+    /// Bumped on every change of the bookmarks, views labelled by them compare it.
+    pub revision: u64,
+}
+
+pub enum BookmarkAction {
+    Add,
+    Jump(usize),
+}
+
+impl BookmarksPanel {
+    pub fn ui(
+        &mut self,
+        ui: &mut Ui,
+        theme: &Theme,
+        list: &mut Vec<Bookmark>,
+        fmt_offset: impl Fn(usize) -> String,
+    ) -> Option<BookmarkAction> {
+        let mut action = None;
+        if ui.button("➕ Add at selection").clicked() {
+            action = Some(BookmarkAction::Add);
+        }
+        let mut remove = None;
+        ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+            for (i, b) in list.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    let name = RichText::new(b.name.as_str()).color(b.color32());
+                    if ui.selectable_label(self.selected == Some(i), name).clicked() {
+                        self.selected = Some(i);
+                        action = Some(BookmarkAction::Jump(b.start));
+                    }
+                    let end = fmt_offset(b.start + b.len);
+                    ui.colored_label(theme.comment, format!("{}..{}", fmt_offset(b.start), end));
+                    if ui.small_button("🗑").clicked() {
+                        remove = Some(i);
+                    }
+                });
+                if !b.comment.is_empty() {
+                    ui.colored_label(theme.comment, b.comment.as_str());
+                }
+            }
+        });
+        if let Some(i) = remove {
+            list.remove(i);
+            self.selected = None;
+            self.revision += 1;
+        }
+        if let Some(b) = self.selected.and_then(|i| list.get_mut(i)) {
+            ui.separator();
+            let mut changed = false;
+            ui.horizontal(|ui| {
+                changed |= ui.color_edit_button_srgb(&mut b.color).changed();
+                changed |= ui
+                    .add(TextEdit::singleline(&mut b.name).hint_text("name"))
+                    .changed();
+            });
+            let comment = TextEdit::multiline(&mut b.comment)
+                .hint_text("comment")
+                .desired_rows(2);
+            changed |= ui.add(comment).changed();
+            if changed {
+                self.revision += 1;
+            }
+        }
+        action
+    }
+}
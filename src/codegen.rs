@@ -0,0 +1,515 @@
+use crate::reads::{self, InputId, Read};
+use crate::style::Theme;
+use egui::{ScrollArea, TextEdit, Ui};
+use std::fmt::Write;
+use std::ops::Range;
+use xeh::prelude::*;
+
+const C_KEYWORDS: &[&str] = &[
+    "auto", "break", "case", "char", "const", "continue", "default", "do", "double", "else",
+    "enum", "extern", "float", "for", "goto", "if", "int", "long", "register", "return", "short",
+    "signed", "sizeof", "static", "struct", "switch", "typedef", "union", "unsigned", "void",
+    "volatile", "while",
+];
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for",
+    "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
+    "self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where",
+    "while", "async", "await", "dyn",
+];
+
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum Lang {
+    #[default]
+    C,
+    Rust,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Endian {
+    Big,
+    Little,
+    /// Both byte orders give the value, e.g. zero.
+    Either,
+    Unknown,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Kind {
+    Int { signed: bool },
+    Real,
+    Bytes,
+}
+
+/// Variable read from the input.
+pub struct Field {
+    pub name: String,
+    pub range: Range<usize>,
+    pub kind: Kind,
+    pub endian: Endian,
+}
+
+impl Field {
+    fn is_byte_aligned(&self) -> bool {
+        self.range.start.is_multiple_of(8) && self.range.end.is_multiple_of(8)
+    }
+
+    fn bytes(&self) -> usize {
+        (self.range.end - self.range.start) / 8
+    }
+
+    fn is_primitive(&self) -> bool {
+        match self.kind {
+            Kind::Int { .. } => [1, 2, 4, 8].contains(&self.bytes()),
+            Kind::Real => [4, 8].contains(&self.bytes()),
+            Kind::Bytes => false,
+        }
+    }
+}
+
+/// Byte order that turns the bytes into the value.
+fn detect_endian(bytes: &[u8], val: &Cell, kind: Kind) -> Endian {
+    if bytes.len() < 2 {
+        return Endian::Either;
+    }
+    let be = bytes.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+    let le = bytes
+        .iter()
+        .rev()
+        .fold(0u64, |acc, &b| (acc << 8) | b as u64);
+    let (is_be, is_le) = match kind {
+        Kind::Int { .. } => {
+            let mask = u64::MAX >> (64 - bytes.len() * 8);
+            match val.to_xint() {
+                Ok(v) => (v as u64 & mask == be, v as u64 & mask == le),
+                Err(_) => (false, false),
+            }
+        }
+        Kind::Real => {
+            let real = |bits: u64| match bytes.len() {
+                4 => f32::from_bits(bits as u32) as f64,
+                _ => f64::from_bits(bits),
+            };
+            match val.to_real() {
+                Ok(v) => (real(be) == v, real(le) == v),
+                Err(_) => (false, false),
+            }
+        }
+        Kind::Bytes => return Endian::Either,
+    };
+    match (is_be, is_le) {
+        (true, true) => Endian::Either,
+        (true, false) => Endian::Big,
+        (false, true) => Endian::Little,
+        (false, false) => Endian::Unknown,
+    }
+}
+
+/// User variables defined from a read of the input, in the order of their offset.
+pub fn fields(
+    reads: &[Read],
+    vars: &[(Xstr, Cell)],
+    boot_len: usize,
+    input: &Xbitstr,
+) -> Vec<Field> {
+    let id = InputId::of(input);
+    let mut fields = Vec::new();
+    for (k, i) in reads::var_reads(reads, vars)
+        .into_iter()
+        .enumerate()
+        .skip(boot_len)
+    {
+        let Some(r) = i.map(|i| &reads[i]) else {
+            continue;
+        };
+        if r.input != id || r.range.start >= r.range.end {
+            continue;
+        }
+        let (name, val) = &vars[k];
+        let kind = if val.to_xint().is_ok() {
+            let signed = val.to_xint().is_ok_and(|v| v < 0);
+            Kind::Int { signed }
+        } else if val.to_real().is_ok() {
+            Kind::Real
+        } else {
+            Kind::Bytes
+        };
+        let aligned = r.range.start.is_multiple_of(8) && r.range.end.is_multiple_of(8);
+        let endian = match input.seek(r.range.start) {
+            Some(bs) if aligned => {
+                let n = (r.range.end - r.range.start) / 8;
+                let bytes: Vec<u8> = bs.iter8().take(n.min(8)).map(|(b, _)| b).collect();
+                detect_endian(&bytes, val, kind)
+            }
+            _ => Endian::Either,
+        };
+        fields.push(Field {
+            name: name.to_string(),
+            range: r.range.clone(),
+            kind,
+            endian,
+        });
+    }
+    fields.sort_by_key(|f| f.range.start);
+    // a struct can't describe the same bytes twice, e.g. after a seek back
+    let mut end = 0;
+    fields.retain(|f| {
+        let keep = f.range.start >= end;
+        if keep {
+            end = f.range.end;
+        }
+        keep
+    });
+    fields
+}
+
+fn identifier(name: &str, keywords: &[&str]) -> String {
+    let mut id: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if id.is_empty()
+        || id.starts_with(|c: char| c.is_ascii_digit())
+        || keywords.contains(&id.as_str())
+    {
+        id.insert(0, '_');
+    }
+    id
+}
+
+fn camel_case(name: &str) -> String {
+    let camel: String = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| {
+            let mut w = w.to_string();
+            w[..1].make_ascii_uppercase();
+            w
+        })
+        .collect();
+    identifier(&camel, RUST_KEYWORDS)
+}
+
+/// Runs of bit fields share the bytes they are packed in.
+enum Member<'a> {
+    Field(&'a Field),
+    Bits(Vec<&'a Field>),
+    Padding(usize),
+}
+
+impl Member<'_> {
+    fn range(&self, start: usize) -> Range<usize> {
+        match self {
+            Member::Field(f) => f.range.clone(),
+            Member::Bits(fs) => {
+                let start = fs[0].range.start / 8 * 8;
+                start..fs[fs.len() - 1].range.end.div_ceil(8) * 8
+            }
+            Member::Padding(n) => start..start + n * 8,
+        }
+    }
+}
+
+/// Lay out the fields from the first byte of the first field.
+fn members(fields: &[Field]) -> (usize, Vec<Member<'_>>) {
+    let base = fields.first().map_or(0, |f| f.range.start / 8 * 8);
+    let mut members = Vec::new();
+    let mut pos = base;
+    let mut i = 0;
+    while i < fields.len() {
+        let f = &fields[i];
+        let member = if f.is_byte_aligned() {
+            i += 1;
+            Member::Field(f)
+        } else {
+            let mut bits = vec![f];
+            i += 1;
+            // keep the bit fields sharing a byte together
+            while i < fields.len()
+                && fields[i].range.start < bits[bits.len() - 1].range.end.div_ceil(8) * 8
+            {
+                bits.push(&fields[i]);
+                i += 1;
+            }
+            Member::Bits(bits)
+        };
+        let range = member.range(pos);
+        if range.start > pos {
+            members.push(Member::Padding((range.start - pos) / 8));
+        }
+        pos = range.end;
+        members.push(member);
+    }
+    (base, members)
+}
+
+fn endian_note(fields: &[Field]) -> String {
+    let multi = || fields.iter().filter(|f| f.is_primitive() && f.bytes() > 1);
+    let big = multi().filter(|f| f.endian == Endian::Big).count();
+    let little = multi().filter(|f| f.endian == Endian::Little).count();
+    match (big, little) {
+        (0, 0) => "byte order could not be told from the values".to_string(),
+        (_, 0) => "multi-byte fields are big endian".to_string(),
+        (0, _) => "multi-byte fields are little endian".to_string(),
+        _ => "mixed byte order, see the field comments".to_string(),
+    }
+}
+
+fn field_note(f: &Field) -> &'static str {
+    match f.endian {
+        Endian::Big if f.bytes() > 1 => ", big endian",
+        Endian::Little if f.bytes() > 1 => ", little endian",
+        Endian::Unknown => ", byte order unknown",
+        _ => "",
+    }
+}
+
+fn c_type(f: &Field) -> &'static str {
+    match (f.kind, f.bytes()) {
+        (Kind::Int { signed: false }, 1) => "uint8_t",
+        (Kind::Int { signed: false }, 2) => "uint16_t",
+        (Kind::Int { signed: false }, 4) => "uint32_t",
+        (Kind::Int { signed: false }, 8) => "uint64_t",
+        (Kind::Int { signed: true }, 1) => "int8_t",
+        (Kind::Int { signed: true }, 2) => "int16_t",
+        (Kind::Int { signed: true }, 4) => "int32_t",
+        (Kind::Int { signed: true }, 8) => "int64_t",
+        (Kind::Real, 4) => "float",
+        (Kind::Real, 8) => "double",
+        _ => "uint8_t",
+    }
+}
+
+fn rust_type(f: &Field) -> String {
+    match (f.kind, f.bytes()) {
+        (Kind::Int { signed }, n @ (1 | 2 | 4 | 8)) => {
+            format!("{}{}", if signed { "i" } else { "u" }, n * 8)
+        }
+        (Kind::Real, 4) => "f32".to_string(),
+        (Kind::Real, 8) => "f64".to_string(),
+        (_, n) => format!("[u8; {}]", n),
+    }
+}
+
+fn bit_list(bits: &[&Field], base: usize) -> String {
+    let list: Vec<String> = bits
+        .iter()
+        .map(|f| {
+            format!(
+                "{} bits {}..{}",
+                f.name,
+                f.range.start - base,
+                f.range.end - base
+            )
+        })
+        .collect();
+    list.join(", ")
+}
+
+pub fn c_header(name: &str, fields: &[Field]) -> String {
+    let name = identifier(name, C_KEYWORDS);
+    let (base, members) = members(fields);
+    let mut out = String::new();
+    let guard = format!("{}_H", name.to_ascii_uppercase());
+    let _ = writeln!(
+        out,
+        "#ifndef {0}\n#define {0}\n\n#include <stdint.h>\n",
+        guard
+    );
+    let _ = writeln!(out, "/* Layout of {}, {}. */", name, endian_note(fields));
+    let _ = writeln!(out, "#pragma pack(push, 1)\nstruct {} {{", name);
+    let mut pos = base;
+    let mut pad = 0;
+    for m in &members {
+        let range = m.range(pos);
+        let offset = (range.start - base) / 8;
+        match m {
+            Member::Field(f) if f.is_primitive() => {
+                let id = identifier(&f.name, C_KEYWORDS);
+                let _ = writeln!(
+                    out,
+                    "    {} {}; /* 0x{:04x}{} */",
+                    c_type(f),
+                    id,
+                    offset,
+                    field_note(f)
+                );
+            }
+            Member::Field(f) => {
+                let id = identifier(&f.name, C_KEYWORDS);
+                let _ = writeln!(
+                    out,
+                    "    uint8_t {}[{}]; /* 0x{:04x} */",
+                    id,
+                    f.bytes(),
+                    offset
+                );
+            }
+            Member::Bits(bits) => {
+                // C bit fields depend on the compiler, the bytes are kept as they are
+                let id = identifier(&bits[0].name, C_KEYWORDS);
+                let n = (range.end - range.start) / 8;
+                let _ = writeln!(
+                    out,
+                    "    uint8_t {}_bits[{}]; /* 0x{:04x} {}, MSB first */",
+                    id,
+                    n,
+                    offset,
+                    bit_list(bits, range.start)
+                );
+            }
+            Member::Padding(n) => {
+                let _ = writeln!(
+                    out,
+                    "    uint8_t reserved{}[{}]; /* 0x{:04x} */",
+                    pad, n, offset
+                );
+                pad += 1;
+            }
+        }
+        pos = pos.max(range.end);
+    }
+    let _ = writeln!(out, "}};\n#pragma pack(pop)\n\n#endif /* {} */", guard);
+    out
+}
+
+pub fn rust_struct(name: &str, fields: &[Field]) -> String {
+    let (base, members) = members(fields);
+    let mut out = String::new();
+    let _ = writeln!(out, "/// Layout of {}, {}.", name, endian_note(fields));
+    let _ = writeln!(out, "#[repr(C, packed)]\n#[derive(Clone, Copy, Debug)]");
+    let _ = writeln!(out, "pub struct {} {{", camel_case(name));
+    let mut pos = base;
+    let mut pad = 0;
+    for m in &members {
+        let range = m.range(pos);
+        let offset = (range.start - base) / 8;
+        match m {
+            Member::Field(f) => {
+                let id = identifier(&f.name, RUST_KEYWORDS);
+                let _ = writeln!(out, "    /// 0x{:04x}{}", offset, field_note(f));
+                let _ = writeln!(out, "    pub {}: {},", id, rust_type(f));
+            }
+            Member::Bits(bits) => {
+                let id = identifier(&bits[0].name, RUST_KEYWORDS);
+                let n = (range.end - range.start) / 8;
+                let _ = writeln!(
+                    out,
+                    "    /// 0x{:04x} {}, MSB first",
+                    offset,
+                    bit_list(bits, range.start)
+                );
+                let _ = writeln!(out, "    pub {}_bits: [u8; {}],", id, n);
+            }
+            Member::Padding(n) => {
+                let _ = writeln!(out, "    /// 0x{:04x}", offset);
+                let _ = writeln!(out, "    pub reserved{}: [u8; {}],", pad, n);
+                pad += 1;
+            }
+        }
+        pos = pos.max(range.end);
+    }
+    out.push_str("}\n");
+    out
+}
+
+#[derive(Default)]
+pub struct StructGen {
+    pub is_open: bool,
+    pub lang: Lang,
+    pub name: String,
+    pub text: String,
+    /// State the text was generated for.
+    pub key: (u64, usize, Lang, String),
+}
+
+pub enum StructGenAction {
+    Copy,
+    Save,
+}
+
+impl StructGen {
+    pub fn extension(&self) -> &'static str {
+        match self.lang {
+            Lang::C => "h",
+            Lang::Rust => "rs",
+        }
+    }
+
+    pub fn generate(&mut self, fields: &[Field]) {
+        self.text = match (fields.is_empty(), self.lang) {
+            (true, _) => String::new(),
+            (false, Lang::C) => c_header(&self.name, fields),
+            (false, Lang::Rust) => rust_struct(&self.name, fields),
+        };
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui, theme: &Theme) -> Option<StructGenAction> {
+        let mut action = None;
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut self.lang, Lang::C, "C header");
+            ui.selectable_value(&mut self.lang, Lang::Rust, "Rust struct");
+            ui.label("Name");
+            ui.text_edit_singleline(&mut self.name);
+        });
+        if self.text.is_empty() {
+            ui.colored_label(
+                theme.comment,
+                "Run a script that reads the input into variables",
+            );
+            return None;
+        }
+        ScrollArea::vertical().max_height(320.0).show(ui, |ui| {
+            let mut text = self.text.as_str();
+            ui.add(
+                TextEdit::multiline(&mut text)
+                    .code_editor()
+                    .desired_width(f32::INFINITY),
+            );
+        });
+        ui.horizontal(|ui| {
+            if ui.button("📋 Copy").clicked() {
+                action = Some(StructGenAction::Copy);
+            }
+            if ui.button("💾 Save...").clicked() {
+                action = Some(StructGenAction::Save);
+            }
+        });
+        action
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, range: Range<usize>, kind: Kind) -> Field {
+        Field {
+            name: name.to_string(),
+            range,
+            kind,
+            endian: Endian::Little,
+        }
+    }
+
+    #[test]
+    fn bit_runs_are_byte_arrays() {
+        let uint = Kind::Int { signed: false };
+        let fields = [
+            field("tag", 0..8, uint),
+            field("flag", 11..12, uint),
+            field("level", 12..20, uint),
+            field("size", 24..56, uint),
+        ];
+        let c = c_header("rec", &fields);
+        assert!(c.contains("uint8_t tag; /* 0x0000 */"));
+        assert!(c.contains(
+            "uint8_t flag_bits[2]; /* 0x0001 flag bits 3..4, level bits 4..12, MSB first */"
+        ));
+        assert!(c.contains("uint32_t size; /* 0x0003, little endian */"));
+        let rust = rust_struct("rec", &fields);
+        assert!(rust.contains("pub flag_bits: [u8; 2],"));
+        assert!(rust.contains("pub size: u32,"));
+    }
+}
@@ -7,7 +7,11 @@ use crate::agent::{Agent, Task};
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum CollabMessage {
     Hello { id: Uuid, name: String },
-    Code { text: String },
+    Code {
+        #[serde(default)]
+        buffer: String,
+        text: String,
+    },
     AgentUpdate { agents: Vec<Agent>, tasks: Vec<Task> },
 }
 
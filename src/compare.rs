@@ -0,0 +1,249 @@
+use crate::style::Theme;
+use egui::{Color32, RichText, Ui};
+use similar::{Algorithm, DiffTag};
+use std::ops::Range;
+
+const COLS: usize = 16;
+const ROWS: usize = 24;
+const INSERT_COLOR: Color32 = Color32::from_rgb(0x4c, 0xaf, 0x50);
+const REPLACE_COLOR: Color32 = Color32::from_rgb(0xff, 0xb3, 0x00);
+
+type Op = (DiffTag, Range<usize>, Range<usize>);
+
+/// Runs of differing bytes at the same offsets, for inputs of the same size.
+fn positional_diff(a: &[u8], b: &[u8]) -> Vec<Op> {
+    let mut ops = Vec::new();
+    let mut i = 0;
+    let n = a.len().min(b.len());
+    while i < n {
+        let same = a[i] == b[i];
+        let run = a[i..n].iter().zip(&b[i..n]).take_while(|(x, y)| (x == y) == same).count();
+        let tag = if same { DiffTag::Equal } else { DiffTag::Replace };
+        ops.push((tag, i..i + run, i..i + run));
+        i += run;
+    }
+    if a.len() > n {
+        ops.push((DiffTag::Delete, n..a.len(), n..n));
+    } else if b.len() > n {
+        ops.push((DiffTag::Insert, n..n, n..b.len()));
+    }
+    ops
+}
+
+pub fn diff(a: &[u8], b: &[u8]) -> Vec<Op> {
+    if a.len() == b.len() {
+        return positional_diff(a, b);
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    let deadline = std::time::Instant::now().checked_add(std::time::Duration::from_secs(1));
+    #[cfg(target_arch = "wasm32")]
+    let deadline = None;
+    #[cfg(target_arch = "wasm32")]
+    if a.len() + b.len() > 256 * 1024 {
+        // without a deadline Myers may take forever on the web
+        return positional_diff(a, b);
+    }
+    similar::capture_diff_slices_deadline(Algorithm::Myers, a, b, deadline)
+        .iter()
+        .map(|op| op.as_tag_tuple())
+        .collect()
+}
+
+#[derive(Default)]
+pub struct Compare {
+    pub is_open: bool,
+    pub name: String,
+    other: Vec<u8>,
+    /// Bytes of the loaded input the diff was computed against.
+    input: Option<Vec<u8>>,
+    ops: Vec<Op>,
+    error: Option<String>,
+    /// Byte offset into the loaded input shown at the top.
+    pub pos: usize,
+    /// Bit position of the hex view and `pos` when they were last in step.
+    synced: (usize, usize),
+}
+
+impl Compare {
+    pub fn set_other(&mut self, name: &str, data: Vec<u8>) {
+        self.name = name.to_string();
+        self.other = data;
+        self.input = None;
+        self.is_open = true;
+    }
+
+    pub fn has_other(&self) -> bool {
+        !self.name.is_empty()
+    }
+
+    /// Diff again, the loaded input has changed.
+    pub fn invalidate(&mut self) {
+        self.input = None;
+    }
+
+    pub fn update(&mut self, input: impl FnOnce() -> Result<Vec<u8>, String>) {
+        if self.input.is_some() || !self.has_other() {
+            return;
+        }
+        let (input, error) = match input() {
+            Ok(input) => (input, None),
+            Err(e) => (Vec::new(), Some(e)),
+        };
+        self.ops = if error.is_none() {
+            diff(&input, &self.other)
+        } else {
+            Vec::new()
+        };
+        self.error = error;
+        self.input = Some(input);
+    }
+
+    /// Follow the hex view if it has moved, return the bit position to move it to if the panes have.
+    pub fn sync(&mut self, view_pos: usize) -> Option<usize> {
+        let moved = (self.pos != self.synced.1).then_some(self.pos * 8);
+        if moved.is_none() && view_pos != self.synced.0 {
+            self.pos = view_pos / 8;
+        }
+        self.synced = (moved.unwrap_or(view_pos), self.pos);
+        moved
+    }
+
+    fn is_diff(op: &Op) -> bool {
+        op.0 != DiffTag::Equal
+    }
+
+    pub fn next_diff(&mut self) {
+        let row = self.pos - self.pos % COLS;
+        if let Some(op) = self.ops.iter().find(|op| Self::is_diff(op) && op.1.start >= row + COLS) {
+            self.pos = op.1.start;
+        }
+    }
+
+    pub fn prev_diff(&mut self) {
+        let row = self.pos - self.pos % COLS;
+        if let Some(op) = self.ops.iter().rev().find(|op| Self::is_diff(op) && op.1.start < row) {
+            self.pos = op.1.start;
+        }
+    }
+
+    /// Offset in the other binary matching the offset in the input.
+    fn map_to_other(&self, pos: usize) -> usize {
+        let i = self.ops.partition_point(|op| op.1.end <= pos);
+        match self.ops.get(i) {
+            Some((_, old, new)) => new.start + (pos - old.start.min(pos)).min(new.len()),
+            None => self.other.len(),
+        }
+    }
+
+    fn summary(&self) -> String {
+        let mut counts = [(0, 0); 3];
+        for (tag, old, new) in &self.ops {
+            let (i, n) = match tag {
+                DiffTag::Insert => (0, new.len()),
+                DiffTag::Delete => (1, old.len()),
+                DiffTag::Replace => (2, old.len().max(new.len())),
+                DiffTag::Equal => continue,
+            };
+            counts[i].0 += 1;
+            counts[i].1 += n;
+        }
+        format!(
+            "{} inserted ({} bytes), {} deleted ({} bytes), {} changed ({} bytes)",
+            counts[0].0, counts[0].1, counts[1].0, counts[1].1, counts[2].0, counts[2].1
+        )
+    }
+
+    /// Colour of the byte, `new` selects the other binary.
+    fn byte_color(&self, pos: usize, new: bool, theme: &Theme) -> Color32 {
+        let range = |op: &Op| if new { op.2.clone() } else { op.1.clone() };
+        let i = self.ops.partition_point(|op| range(op).end <= pos);
+        match self.ops.get(i).map(|op| op.0) {
+            Some(DiffTag::Insert) => INSERT_COLOR,
+            Some(DiffTag::Delete) => theme.error,
+            Some(DiffTag::Replace) => REPLACE_COLOR,
+            _ => theme.code,
+        }
+    }
+
+    fn ui_dump(&self, ui: &mut Ui, theme: &Theme, data: &[u8], from: usize, new: bool) {
+        ui.vertical(|ui| {
+            ui.spacing_mut().item_spacing = egui::vec2(0.0, 2.0);
+            for row in 0..ROWS {
+                let start = from + row * COLS;
+                ui.horizontal(|ui| {
+                    ui.colored_label(theme.comment, format!("{:06x} ", start));
+                    for pos in start..start + COLS {
+                        let text = match data.get(pos) {
+                            Some(b) => RichText::new(format!(" {:02x}", b))
+                                .color(self.byte_color(pos, new, theme)),
+                            None => RichText::new("   "),
+                        };
+                        ui.label(text.monospace());
+                    }
+                });
+            }
+        });
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui, theme: &Theme) {
+        let (prev, next) = ui
+            .horizontal(|ui| {
+                let prev = ui.button("⏶ Prev Diff").clicked();
+                let next = ui.button("⏷ Next Diff").clicked();
+                if let Some(e) = &self.error {
+                    ui.colored_label(theme.error, e);
+                } else if self.input.is_some() {
+                    ui.colored_label(theme.comment, self.summary());
+                }
+                (prev, next)
+            })
+            .inner;
+        if prev {
+            self.prev_diff();
+        }
+        if next {
+            self.next_diff();
+        }
+        let Some(input) = &self.input else {
+            ui.colored_label(theme.comment, "Open a binary to compare with the loaded one");
+            return;
+        };
+        ui.separator();
+        let resp = ui.horizontal(|ui| {
+            let pos = self.pos - self.pos % COLS;
+            ui.vertical(|ui| {
+                ui.colored_label(theme.text, "input");
+                self.ui_dump(ui, theme, input, pos, false);
+            });
+            ui.separator();
+            ui.vertical(|ui| {
+                ui.colored_label(theme.text, self.name.as_str());
+                let other_pos = self.map_to_other(pos);
+                self.ui_dump(ui, theme, &self.other, other_pos - other_pos % COLS, true);
+            });
+        });
+        if resp.response.hovered() {
+            let dy = ui.input(|i| i.raw_scroll_delta.y);
+            let rows = (-dy / 16.0) as isize;
+            let max = input.len().max(self.other.len());
+            self.pos = (self.pos as isize + rows * COLS as isize).clamp(0, max as isize) as usize;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panes_follow_the_hex_view_and_back() {
+        let mut c = Compare::default();
+        assert_eq!(c.sync(0x80), None);
+        assert_eq!(c.pos, 0x10);
+        c.pos = 0x40;
+        assert_eq!(c.sync(0x80), Some(0x200));
+        // nothing moved since
+        assert_eq!(c.sync(0x200), None);
+        assert_eq!(c.pos, 0x40);
+    }
+}
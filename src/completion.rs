@@ -0,0 +1,115 @@
+use crate::style::Theme;
+use egui::{Area, Context, Frame, Id, Key, Modifiers, Order, Pos2, RichText, ScrollArea};
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+
+const MAX_ITEMS: usize = 12;
+
+#[derive(Clone)]
+pub struct Candidate {
+    pub name: String,
+    pub kind: &'static str,
+    pub stack: String,
+    pub text: String,
+}
+
+#[derive(Default)]
+pub struct Completion {
+    pub is_open: bool,
+    pub selected: usize,
+    pub items: Vec<Candidate>,
+    matcher: SkimMatcherV2,
+}
+
+impl Completion {
+    /// Rank candidates against the word being typed, open the popup if anything matched.
+    pub fn update(&mut self, prefix: &str, candidates: Vec<Candidate>) {
+        let mut scored: Vec<(i64, Candidate)> = candidates
+            .into_iter()
+            .filter_map(|c| self.matcher.fuzzy_match(&c.name, prefix).map(|score| (score, c)))
+            .collect();
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.name.len().cmp(&b.1.name.len())));
+        self.items = scored.into_iter().take(MAX_ITEMS).map(|(_, c)| c).collect();
+        if self.items.len() == 1 && self.items[0].name == prefix {
+            // nothing left to complete
+            self.items.clear();
+        }
+        self.selected = 0;
+        self.is_open = !self.items.is_empty();
+    }
+
+    pub fn close(&mut self) {
+        self.is_open = false;
+        self.items.clear();
+    }
+
+    /// Consume navigation keys before the editor sees them, return the accepted word.
+    pub fn handle_keys(&mut self, ctx: &Context) -> Option<String> {
+        if !self.is_open {
+            return None;
+        }
+        let (down, up, accept, cancel) = ctx.input_mut(|i| {
+            (
+                i.consume_key(Modifiers::NONE, Key::ArrowDown),
+                i.consume_key(Modifiers::NONE, Key::ArrowUp),
+                i.consume_key(Modifiers::NONE, Key::Tab) || i.consume_key(Modifiers::NONE, Key::Enter),
+                i.consume_key(Modifiers::NONE, Key::Escape),
+            )
+        });
+        if down {
+            self.selected = (self.selected + 1).min(self.items.len().saturating_sub(1));
+        }
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        if cancel {
+            self.close();
+            return None;
+        }
+        if accept {
+            let word = self.items.get(self.selected).map(|c| c.name.clone());
+            self.close();
+            return word;
+        }
+        None
+    }
+
+    pub fn show(&mut self, ctx: &Context, pos: Pos2, theme: &Theme) -> Option<String> {
+        if !self.is_open {
+            return None;
+        }
+        let mut accepted = None;
+        Area::new(Id::new("completion"))
+            .order(Order::Foreground)
+            .fixed_pos(pos)
+            .show(ctx, |ui| {
+                Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_max_width(500.0);
+                    ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+                        for (i, c) in self.items.iter().enumerate() {
+                            ui.horizontal(|ui| {
+                                let name = RichText::new(c.name.as_str()).color(theme.selection);
+                                if ui.selectable_label(i == self.selected, name).clicked() {
+                                    accepted = Some(c.name.clone());
+                                }
+                                if !c.stack.is_empty() {
+                                    ui.colored_label(theme.comment, format!("( {} )", c.stack));
+                                }
+                                ui.colored_label(theme.comment, c.kind);
+                            });
+                        }
+                    });
+                    if let Some(c) = self.items.get(self.selected) {
+                        if !c.text.is_empty() {
+                            ui.separator();
+                            ui.label(c.text.as_str());
+                        }
+                    }
+                });
+            });
+        if accepted.is_some() {
+            self.close();
+        }
+        accepted
+    }
+}
@@ -0,0 +1,184 @@
+use crate::binary;
+use miniz_oxide::inflate::stream::{self, InflateState};
+use miniz_oxide::{DataFormat, MZError, MZFlush, MZStatus};
+use std::io::Read;
+use xeh::prelude::*;
+
+/// Output above this size is treated as an error rather than filling the memory.
+const MAX_OUTPUT: usize = 256 * 1024 * 1024;
+
+const LZ4_FRAME_MAGIC: [u8; 4] = [0x04, 0x22, 0x4d, 0x18];
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Codec {
+    Zlib,
+    Gzip,
+    Deflate,
+    /// LZ4 frame, or a block prepended with its little endian u32 size.
+    Lz4,
+}
+
+impl Codec {
+    pub const ALL: [Codec; 4] = [Codec::Zlib, Codec::Gzip, Codec::Deflate, Codec::Lz4];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Codec::Zlib => "zlib",
+            Codec::Gzip => "gzip",
+            Codec::Deflate => "deflate",
+            Codec::Lz4 => "lz4",
+        }
+    }
+}
+
+/// Length of the gzip member header, RFC 1952.
+fn gzip_header_len(data: &[u8]) -> Result<usize, String> {
+    const FHCRC: u8 = 2;
+    const FEXTRA: u8 = 4;
+    const FNAME: u8 = 8;
+    const FCOMMENT: u8 = 16;
+    if data.len() < 10 || data[0..3] != [0x1f, 0x8b, 8] {
+        return Err("not a gzip stream".to_string());
+    }
+    let flags = data[3];
+    let mut pos = 10;
+    let truncated = || "truncated gzip header".to_string();
+    if flags & FEXTRA != 0 {
+        let len = data.get(pos..pos + 2).ok_or_else(truncated)?;
+        pos += 2 + u16::from_le_bytes([len[0], len[1]]) as usize;
+    }
+    for flag in [FNAME, FCOMMENT] {
+        if flags & flag != 0 {
+            let end = data.get(pos..).ok_or_else(truncated)?;
+            pos += end.iter().position(|&b| b == 0).ok_or_else(truncated)? + 1;
+        }
+    }
+    if flags & FHCRC != 0 {
+        pos += 2;
+    }
+    if pos > data.len() {
+        return Err(truncated());
+    }
+    Ok(pos)
+}
+
+/// Inflate a zlib or raw deflate stream, stop at its end and count the input bytes it took.
+fn inflate(data: &[u8], format: DataFormat) -> Result<(Vec<u8>, usize), String> {
+    let mut state = InflateState::new_boxed(format);
+    let mut buf = vec![0; 64 * 1024];
+    let mut out = Vec::new();
+    let mut pos = 0;
+    loop {
+        let res = stream::inflate(&mut state, &data[pos..], &mut buf, MZFlush::None);
+        pos += res.bytes_consumed;
+        out.extend_from_slice(&buf[..res.bytes_written]);
+        if out.len() > MAX_OUTPUT {
+            return Err("output is too large".to_string());
+        }
+        match res.status {
+            Ok(MZStatus::StreamEnd) => return Ok((out, pos)),
+            Ok(_) => (),
+            Err(MZError::Buf) => return Err("truncated stream".to_string()),
+            Err(e) => return Err(format!("{:?}", e)),
+        }
+    }
+}
+
+/// Decompressed data and the length of the compressed stream, input after its end is ignored.
+pub fn decompress(codec: Codec, data: &[u8]) -> Result<(Vec<u8>, usize), String> {
+    match codec {
+        Codec::Zlib => inflate(data, DataFormat::Zlib),
+        Codec::Deflate => inflate(data, DataFormat::Raw),
+        Codec::Gzip => {
+            const TRAILER_LEN: usize = 8;
+            let start = gzip_header_len(data)?;
+            let (out, len) = inflate(&data[start..], DataFormat::Raw)?;
+            Ok((out, (start + len + TRAILER_LEN).min(data.len())))
+        }
+        Codec::Lz4 if data.starts_with(&LZ4_FRAME_MAGIC) => {
+            let mut rest = data;
+            let mut out = Vec::new();
+            // the decoder stops at the end mark of the first frame
+            lz4_flex::frame::FrameDecoder::new(&mut rest)
+                .take(MAX_OUTPUT as u64 + 1)
+                .read_to_end(&mut out)
+                .map_err(|e| e.to_string())?;
+            if out.len() > MAX_OUTPUT {
+                return Err("output is too large".to_string());
+            }
+            Ok((out, data.len() - rest.len()))
+        }
+        Codec::Lz4 => {
+            let size = data.get(..4).ok_or("truncated lz4 block")?;
+            if u32::from_le_bytes(size.try_into().unwrap()) as usize > MAX_OUTPUT {
+                return Err("output is too large".to_string());
+            }
+            let out =
+                lz4_flex::block::decompress_size_prepended(data).map_err(|e| e.to_string())?;
+            Ok((out, data.len()))
+        }
+    }
+}
+
+fn inflate_word(xs: &mut Xstate, codec: Codec) -> Xresult {
+    let bs = xs.pop_data()?;
+    let bytes = binary::to_bytes(bs.bitstr()?);
+    match decompress(codec, &bytes) {
+        Ok((out, _)) => xs.push_data(Cell::from(Xbitstr::from(out))),
+        Err(e) => Err(Xerr::ErrorMsg(Xstr::from(format!(
+            "{}: {}",
+            codec.name(),
+            e
+        )))),
+    }
+}
+
+fn inflate_zlib(xs: &mut Xstate) -> Xresult {
+    inflate_word(xs, Codec::Zlib)
+}
+
+fn inflate_gzip(xs: &mut Xstate) -> Xresult {
+    inflate_word(xs, Codec::Gzip)
+}
+
+fn inflate_deflate(xs: &mut Xstate) -> Xresult {
+    inflate_word(xs, Codec::Deflate)
+}
+
+fn inflate_lz4(xs: &mut Xstate) -> Xresult {
+    inflate_word(xs, Codec::Lz4)
+}
+
+/// `bitstr inflate/zlib -- bitstr` and the same for gzip, deflate and lz4.
+pub fn install(xs: &mut Xstate) -> Xresult {
+    xs.defword("inflate/zlib", inflate_zlib)?;
+    xs.defword("inflate/gzip", inflate_gzip)?;
+    xs.defword("inflate/deflate", inflate_deflate)?;
+    xs.defword("inflate/lz4", inflate_lz4)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use miniz_oxide::deflate;
+
+    #[test]
+    fn stops_at_the_end_of_the_stream() {
+        let text = b"hello, hello, hello".to_vec();
+        for (codec, mut data) in [
+            (Codec::Zlib, deflate::compress_to_vec_zlib(&text, 6)),
+            (Codec::Deflate, deflate::compress_to_vec(&text, 6)),
+        ] {
+            let len = data.len();
+            data.extend_from_slice(b"trailing");
+            assert_eq!(decompress(codec, &data), Ok((text.clone(), len)));
+        }
+    }
+
+    #[test]
+    fn truncated_stream() {
+        let data = deflate::compress_to_vec_zlib(b"hello, hello, hello", 6);
+        assert!(decompress(Codec::Zlib, &data[..data.len() - 6]).is_err());
+    }
+}
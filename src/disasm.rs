@@ -0,0 +1,917 @@
+use crate::style::Theme;
+use egui::{ComboBox, RichText, ScrollArea, TextEdit, Ui};
+use std::collections::HashMap;
+
+/// Bytes decoded at once, larger ranges are cut.
+pub const MAX_BYTES: usize = 1024 * 1024;
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Cpu {
+    #[default]
+    M6502,
+    Z80,
+    Lr35902,
+    Thumb,
+}
+
+impl Cpu {
+    pub const ALL: [Cpu; 4] = [Cpu::M6502, Cpu::Z80, Cpu::Lr35902, Cpu::Thumb];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Cpu::M6502 => "6502",
+            Cpu::Z80 => "Z80",
+            Cpu::Lr35902 => "Game Boy LR35902",
+            Cpu::Thumb => "ARM Thumb",
+        }
+    }
+
+    pub fn fmt_addr(&self, addr: u32) -> String {
+        match self {
+            Cpu::Thumb => format!("0x{:08x}", addr),
+            _ => format!("${:04X}", addr),
+        }
+    }
+}
+
+pub struct Insn {
+    /// Byte offset in the decoded range.
+    pub offset: usize,
+    pub addr: u32,
+    pub bytes: Vec<u8>,
+    /// Text up to the jump target, all of it for the other instructions.
+    pub text: String,
+    pub target: Option<u32>,
+}
+
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    start: usize,
+    pos: usize,
+}
+
+impl Cursor<'_> {
+    fn u8(&mut self) -> Option<u8> {
+        let b = *self.bytes.get(self.pos)?;
+        self.pos += 1;
+        Some(b)
+    }
+
+    fn i8(&mut self) -> Option<i8> {
+        self.u8().map(|b| b as i8)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        let lo = self.u8()?;
+        let hi = self.u8()?;
+        Some(u16::from_le_bytes([lo, hi]))
+    }
+
+    fn len(&self) -> u32 {
+        (self.pos - self.start) as u32
+    }
+}
+
+/// Mnemonic and jump target of one instruction.
+type Decoded = (String, Option<u32>);
+
+#[derive(Clone, Copy)]
+enum Mode {
+    Imp,
+    Acc,
+    Imm,
+    Zp,
+    Zpx,
+    Zpy,
+    Abs,
+    Abx,
+    Aby,
+    Ind,
+    Izx,
+    Izy,
+    Rel,
+}
+
+const M6502_OPS: &[(u8, &str, Mode)] = {
+    use Mode::*;
+    &[
+        (0x69, "ADC", Imm),
+        (0x65, "ADC", Zp),
+        (0x75, "ADC", Zpx),
+        (0x6d, "ADC", Abs),
+        (0x7d, "ADC", Abx),
+        (0x79, "ADC", Aby),
+        (0x61, "ADC", Izx),
+        (0x71, "ADC", Izy),
+        (0x29, "AND", Imm),
+        (0x25, "AND", Zp),
+        (0x35, "AND", Zpx),
+        (0x2d, "AND", Abs),
+        (0x3d, "AND", Abx),
+        (0x39, "AND", Aby),
+        (0x21, "AND", Izx),
+        (0x31, "AND", Izy),
+        (0x0a, "ASL", Acc),
+        (0x06, "ASL", Zp),
+        (0x16, "ASL", Zpx),
+        (0x0e, "ASL", Abs),
+        (0x1e, "ASL", Abx),
+        (0x90, "BCC", Rel),
+        (0xb0, "BCS", Rel),
+        (0xf0, "BEQ", Rel),
+        (0x30, "BMI", Rel),
+        (0xd0, "BNE", Rel),
+        (0x10, "BPL", Rel),
+        (0x50, "BVC", Rel),
+        (0x70, "BVS", Rel),
+        (0x24, "BIT", Zp),
+        (0x2c, "BIT", Abs),
+        (0x00, "BRK", Imp),
+        (0x18, "CLC", Imp),
+        (0xd8, "CLD", Imp),
+        (0x58, "CLI", Imp),
+        (0xb8, "CLV", Imp),
+        (0xc9, "CMP", Imm),
+        (0xc5, "CMP", Zp),
+        (0xd5, "CMP", Zpx),
+        (0xcd, "CMP", Abs),
+        (0xdd, "CMP", Abx),
+        (0xd9, "CMP", Aby),
+        (0xc1, "CMP", Izx),
+        (0xd1, "CMP", Izy),
+        (0xe0, "CPX", Imm),
+        (0xe4, "CPX", Zp),
+        (0xec, "CPX", Abs),
+        (0xc0, "CPY", Imm),
+        (0xc4, "CPY", Zp),
+        (0xcc, "CPY", Abs),
+        (0xc6, "DEC", Zp),
+        (0xd6, "DEC", Zpx),
+        (0xce, "DEC", Abs),
+        (0xde, "DEC", Abx),
+        (0xca, "DEX", Imp),
+        (0x88, "DEY", Imp),
+        (0x49, "EOR", Imm),
+        (0x45, "EOR", Zp),
+        (0x55, "EOR", Zpx),
+        (0x4d, "EOR", Abs),
+        (0x5d, "EOR", Abx),
+        (0x59, "EOR", Aby),
+        (0x41, "EOR", Izx),
+        (0x51, "EOR", Izy),
+        (0xe6, "INC", Zp),
+        (0xf6, "INC", Zpx),
+        (0xee, "INC", Abs),
+        (0xfe, "INC", Abx),
+        (0xe8, "INX", Imp),
+        (0xc8, "INY", Imp),
+        (0x4c, "JMP", Abs),
+        (0x6c, "JMP", Ind),
+        (0x20, "JSR", Abs),
+        (0xa9, "LDA", Imm),
+        (0xa5, "LDA", Zp),
+        (0xb5, "LDA", Zpx),
+        (0xad, "LDA", Abs),
+        (0xbd, "LDA", Abx),
+        (0xb9, "LDA", Aby),
+        (0xa1, "LDA", Izx),
+        (0xb1, "LDA", Izy),
+        (0xa2, "LDX", Imm),
+        (0xa6, "LDX", Zp),
+        (0xb6, "LDX", Zpy),
+        (0xae, "LDX", Abs),
+        (0xbe, "LDX", Aby),
+        (0xa0, "LDY", Imm),
+        (0xa4, "LDY", Zp),
+        (0xb4, "LDY", Zpx),
+        (0xac, "LDY", Abs),
+        (0xbc, "LDY", Abx),
+        (0x4a, "LSR", Acc),
+        (0x46, "LSR", Zp),
+        (0x56, "LSR", Zpx),
+        (0x4e, "LSR", Abs),
+        (0x5e, "LSR", Abx),
+        (0xea, "NOP", Imp),
+        (0x09, "ORA", Imm),
+        (0x05, "ORA", Zp),
+        (0x15, "ORA", Zpx),
+        (0x0d, "ORA", Abs),
+        (0x1d, "ORA", Abx),
+        (0x19, "ORA", Aby),
+        (0x01, "ORA", Izx),
+        (0x11, "ORA", Izy),
+        (0x48, "PHA", Imp),
+        (0x08, "PHP", Imp),
+        (0x68, "PLA", Imp),
+        (0x28, "PLP", Imp),
+        (0x2a, "ROL", Acc),
+        (0x26, "ROL", Zp),
+        (0x36, "ROL", Zpx),
+        (0x2e, "ROL", Abs),
+        (0x3e, "ROL", Abx),
+        (0x6a, "ROR", Acc),
+        (0x66, "ROR", Zp),
+        (0x76, "ROR", Zpx),
+        (0x6e, "ROR", Abs),
+        (0x7e, "ROR", Abx),
+        (0x40, "RTI", Imp),
+        (0x60, "RTS", Imp),
+        (0xe9, "SBC", Imm),
+        (0xe5, "SBC", Zp),
+        (0xf5, "SBC", Zpx),
+        (0xed, "SBC", Abs),
+        (0xfd, "SBC", Abx),
+        (0xf9, "SBC", Aby),
+        (0xe1, "SBC", Izx),
+        (0xf1, "SBC", Izy),
+        (0x38, "SEC", Imp),
+        (0xf8, "SED", Imp),
+        (0x78, "SEI", Imp),
+        (0x85, "STA", Zp),
+        (0x95, "STA", Zpx),
+        (0x8d, "STA", Abs),
+        (0x9d, "STA", Abx),
+        (0x99, "STA", Aby),
+        (0x81, "STA", Izx),
+        (0x91, "STA", Izy),
+        (0x86, "STX", Zp),
+        (0x96, "STX", Zpy),
+        (0x8e, "STX", Abs),
+        (0x84, "STY", Zp),
+        (0x94, "STY", Zpx),
+        (0x8c, "STY", Abs),
+        (0xaa, "TAX", Imp),
+        (0xa8, "TAY", Imp),
+        (0xba, "TSX", Imp),
+        (0x8a, "TXA", Imp),
+        (0x9a, "TXS", Imp),
+        (0x98, "TYA", Imp),
+    ]
+};
+
+fn m6502(
+    c: &mut Cursor<'_>,
+    addr: u32,
+    table: &[Option<(&'static str, Mode)>; 256],
+) -> Option<Decoded> {
+    let (name, mode) = table[c.u8()? as usize]?;
+    let text = match mode {
+        Mode::Imp => name.to_string(),
+        Mode::Acc => format!("{} A", name),
+        Mode::Imm => format!("{} #${:02X}", name, c.u8()?),
+        Mode::Zp => format!("{} ${:02X}", name, c.u8()?),
+        Mode::Zpx => format!("{} ${:02X},X", name, c.u8()?),
+        Mode::Zpy => format!("{} ${:02X},Y", name, c.u8()?),
+        Mode::Abs => {
+            let a = c.u16()?;
+            if name == "JMP" || name == "JSR" {
+                return Some((format!("{} ", name), Some(a as u32)));
+            }
+            format!("{} ${:04X}", name, a)
+        }
+        Mode::Abx => format!("{} ${:04X},X", name, c.u16()?),
+        Mode::Aby => format!("{} ${:04X},Y", name, c.u16()?),
+        Mode::Ind => format!("{} (${:04X})", name, c.u16()?),
+        Mode::Izx => format!("{} (${:02X},X)", name, c.u8()?),
+        Mode::Izy => format!("{} (${:02X}),Y", name, c.u8()?),
+        Mode::Rel => {
+            let d = c.i8()? as i32;
+            let target = (addr as i32 + c.len() as i32 + d) as u32 & 0xffff;
+            return Some((format!("{} ", name), Some(target)));
+        }
+    };
+    Some((text, None))
+}
+
+const R: [&str; 8] = ["B", "C", "D", "E", "H", "L", "(HL)", "A"];
+const RP: [&str; 4] = ["BC", "DE", "HL", "SP"];
+const RP2: [&str; 4] = ["BC", "DE", "HL", "AF"];
+const CC: [&str; 8] = ["NZ", "Z", "NC", "C", "PO", "PE", "P", "M"];
+const ALU: [&str; 8] = [
+    "ADD A,", "ADC A,", "SUB ", "SBC A,", "AND ", "XOR ", "OR ", "CP ",
+];
+const ROT: [&str; 8] = ["RLC", "RRC", "RL", "RR", "SLA", "SRA", "SLL", "SRL"];
+const ACC_OPS: [&str; 8] = ["RLCA", "RRCA", "RLA", "RRA", "DAA", "CPL", "SCF", "CCF"];
+
+/// Z80 and the Game Boy CPU, decoded by the x/y/z fields of the opcode.
+struct Z80<'a, 'b> {
+    c: &'a mut Cursor<'b>,
+    addr: u32,
+    gb: bool,
+    /// `IX` or `IY` after a DD or FD prefix.
+    index: Option<&'static str>,
+}
+
+impl Z80<'_, '_> {
+    fn r(&mut self, i: u8, plain_hl: bool) -> Option<String> {
+        Some(match (self.index, i) {
+            (Some(ix), 6) => {
+                let d = self.c.i8()?;
+                format!("({}{:+})", ix, d)
+            }
+            (Some(ix), 4) if !plain_hl => format!("{}H", ix),
+            (Some(ix), 5) if !plain_hl => format!("{}L", ix),
+            _ => R[i as usize].to_string(),
+        })
+    }
+
+    fn hl(&self) -> &'static str {
+        self.index.unwrap_or("HL")
+    }
+
+    fn rp(&self, p: u8) -> &'static str {
+        if p == 2 {
+            self.hl()
+        } else {
+            RP[p as usize]
+        }
+    }
+
+    fn rp2(&self, p: u8) -> &'static str {
+        if p == 2 {
+            self.hl()
+        } else {
+            RP2[p as usize]
+        }
+    }
+
+    fn rel(&mut self, text: String) -> Option<Decoded> {
+        let d = self.c.i8()? as i32;
+        let target = (self.addr as i32 + self.c.len() as i32 + d) as u32 & 0xffff;
+        Some((text, Some(target)))
+    }
+
+    fn decode(&mut self) -> Option<Decoded> {
+        let op = self.c.u8()?;
+        if !self.gb && self.index.is_none() && (op == 0xdd || op == 0xfd) {
+            let next = *self.c.bytes.get(self.c.pos)?;
+            if [0xdd, 0xed, 0xfd].contains(&next) {
+                // a prefix followed by another acts as a NOP
+                return None;
+            }
+            self.index = Some(if op == 0xdd { "IX" } else { "IY" });
+            return self.decode();
+        }
+        let (x, y, z) = (op >> 6, (op >> 3) & 7, op & 7);
+        let (p, q) = (y >> 1, y & 1);
+        let text = match (x, z) {
+            (0, 0) => match y {
+                0 => "NOP".to_string(),
+                1 if self.gb => return Some((format!("LD (${:04X}),SP", self.c.u16()?), None)),
+                1 => "EX AF,AF'".to_string(),
+                2 if self.gb => {
+                    self.c.u8()?;
+                    "STOP".to_string()
+                }
+                2 => return self.rel("DJNZ ".to_string()),
+                3 => return self.rel("JR ".to_string()),
+                _ => return self.rel(format!("JR {},", CC[y as usize - 4])),
+            },
+            (0, 1) if q == 0 => format!("LD {},${:04X}", self.rp(p), self.c.u16()?),
+            (0, 1) => format!("ADD {},{}", self.hl(), self.rp(p)),
+            (0, 2) => match (p, q, self.gb) {
+                (0, 0, _) => "LD (BC),A".to_string(),
+                (1, 0, _) => "LD (DE),A".to_string(),
+                (0, 1, _) => "LD A,(BC)".to_string(),
+                (1, 1, _) => "LD A,(DE)".to_string(),
+                (2, 0, true) => "LD (HL+),A".to_string(),
+                (3, 0, true) => "LD (HL-),A".to_string(),
+                (2, 1, true) => "LD A,(HL+)".to_string(),
+                (3, 1, true) => "LD A,(HL-)".to_string(),
+                (2, 0, false) => format!("LD (${:04X}),{}", self.c.u16()?, self.hl()),
+                (3, 0, false) => format!("LD (${:04X}),A", self.c.u16()?),
+                (2, 1, false) => format!("LD {},(${:04X})", self.hl(), self.c.u16()?),
+                _ => format!("LD A,(${:04X})", self.c.u16()?),
+            },
+            (0, 3) => format!("{} {}", if q == 0 { "INC" } else { "DEC" }, self.rp(p)),
+            (0, 4) => format!("INC {}", self.r(y, false)?),
+            (0, 5) => format!("DEC {}", self.r(y, false)?),
+            (0, 6) => {
+                let r = self.r(y, false)?;
+                format!("LD {},${:02X}", r, self.c.u8()?)
+            }
+            (0, _) => ACC_OPS[y as usize].to_string(),
+            (1, _) if y == 6 && z == 6 => "HALT".to_string(),
+            (1, _) => {
+                let plain_hl = y == 6 || z == 6;
+                let dst = self.r(y, plain_hl)?;
+                format!("LD {},{}", dst, self.r(z, plain_hl)?)
+            }
+            (2, _) => format!("{}{}", ALU[y as usize], self.r(z, false)?),
+            (3, 0) if self.gb && y >= 4 => match y {
+                4 => format!("LDH (${:02X}),A", self.c.u8()?),
+                5 => format!("ADD SP,{}", self.c.i8()?),
+                6 => format!("LDH A,(${:02X})", self.c.u8()?),
+                _ => format!("LD HL,SP{:+}", self.c.i8()?),
+            },
+            (3, 0) => format!("RET {}", CC[y as usize]),
+            (3, 1) if q == 0 => format!("POP {}", self.rp2(p)),
+            (3, 1) => match p {
+                0 => "RET".to_string(),
+                1 if self.gb => "RETI".to_string(),
+                1 => "EXX".to_string(),
+                2 => format!("JP ({})", self.hl()),
+                _ => format!("LD SP,{}", self.hl()),
+            },
+            (3, 2) if self.gb && y >= 4 => match y {
+                4 => "LD ($FF00+C),A".to_string(),
+                5 => format!("LD (${:04X}),A", self.c.u16()?),
+                6 => "LD A,($FF00+C)".to_string(),
+                _ => format!("LD A,(${:04X})", self.c.u16()?),
+            },
+            (3, 2) => {
+                return Some((
+                    format!("JP {},", CC[y as usize]),
+                    Some(self.c.u16()? as u32),
+                ))
+            }
+            (3, 3) => match y {
+                0 => return Some(("JP ".to_string(), Some(self.c.u16()? as u32))),
+                1 => return self.cb(),
+                6 => "DI".to_string(),
+                7 => "EI".to_string(),
+                _ if self.gb => return None,
+                2 => format!("OUT (${:02X}),A", self.c.u8()?),
+                3 => format!("IN A,(${:02X})", self.c.u8()?),
+                4 => format!("EX (SP),{}", self.hl()),
+                _ => "EX DE,HL".to_string(),
+            },
+            (3, 4) if self.gb && y >= 4 => return None,
+            (3, 4) => {
+                return Some((
+                    format!("CALL {},", CC[y as usize]),
+                    Some(self.c.u16()? as u32),
+                ))
+            }
+            (3, 5) if q == 0 => format!("PUSH {}", self.rp2(p)),
+            (3, 5) if p == 0 => return Some(("CALL ".to_string(), Some(self.c.u16()? as u32))),
+            (3, 5) if p == 2 && !self.gb && self.index.is_none() => return self.ed(),
+            (3, 5) => return None,
+            (3, 6) => format!("{}${:02X}", ALU[y as usize], self.c.u8()?),
+            _ => return Some(("RST ".to_string(), Some(y as u32 * 8))),
+        };
+        Some((text, None))
+    }
+
+    fn cb(&mut self) -> Option<Decoded> {
+        // the displacement of DD CB d op comes before the opcode
+        let reg = match self.index {
+            Some(ix) => {
+                let d = self.c.i8()?;
+                Some(format!("({}{:+})", ix, d))
+            }
+            None => None,
+        };
+        let op = self.c.u8()?;
+        let (x, y, z) = (op >> 6, (op >> 3) & 7, op & 7);
+        let r = reg.unwrap_or_else(|| R[z as usize].to_string());
+        let text = match x {
+            0 if self.gb && y == 6 => format!("SWAP {}", r),
+            0 => format!("{} {}", ROT[y as usize], r),
+            1 => format!("BIT {},{}", y, r),
+            2 => format!("RES {},{}", y, r),
+            _ => format!("SET {},{}", y, r),
+        };
+        Some((text, None))
+    }
+
+    fn ed(&mut self) -> Option<Decoded> {
+        const IM: [&str; 8] = ["0", "0/1", "1", "2", "0", "0/1", "1", "2"];
+        const BLOCK: [[&str; 4]; 4] = [
+            ["LDI", "CPI", "INI", "OUTI"],
+            ["LDD", "CPD", "IND", "OUTD"],
+            ["LDIR", "CPIR", "INIR", "OTIR"],
+            ["LDDR", "CPDR", "INDR", "OTDR"],
+        ];
+        const MISC: [&str; 8] = [
+            "LD I,A", "LD R,A", "LD A,I", "LD A,R", "RRD", "RLD", "NOP", "NOP",
+        ];
+        let op = self.c.u8()?;
+        let (x, y, z) = (op >> 6, (op >> 3) & 7, op & 7);
+        let (p, q) = (y >> 1, y & 1);
+        let text = match (x, z) {
+            (1, 0) if y == 6 => "IN (C)".to_string(),
+            (1, 0) => format!("IN {},(C)", R[y as usize]),
+            (1, 1) if y == 6 => "OUT (C),0".to_string(),
+            (1, 1) => format!("OUT (C),{}", R[y as usize]),
+            (1, 2) => format!(
+                "{} HL,{}",
+                if q == 0 { "SBC" } else { "ADC" },
+                RP[p as usize]
+            ),
+            (1, 3) if q == 0 => format!("LD (${:04X}),{}", self.c.u16()?, RP[p as usize]),
+            (1, 3) => format!("LD {},(${:04X})", RP[p as usize], self.c.u16()?),
+            (1, 4) => "NEG".to_string(),
+            (1, 5) => if y == 1 { "RETI" } else { "RETN" }.to_string(),
+            (1, 6) => format!("IM {}", IM[y as usize]),
+            (1, _) => MISC[y as usize].to_string(),
+            (2, _) if z <= 3 && y >= 4 => BLOCK[y as usize - 4][z as usize].to_string(),
+            _ => return None,
+        };
+        Some((text, None))
+    }
+}
+
+fn thumb_regs(list: u16, extra: Option<&str>) -> String {
+    let mut regs: Vec<String> = (0..8)
+        .filter(|i| list & (1 << i) != 0)
+        .map(|i| format!("r{}", i))
+        .collect();
+    regs.extend(extra.map(str::to_string));
+    format!("{{{}}}", regs.join(", "))
+}
+
+fn thumb(c: &mut Cursor<'_>, addr: u32) -> Option<Decoded> {
+    const ALU: [&str; 16] = [
+        "AND", "EOR", "LSL", "LSR", "ASR", "ADC", "SBC", "ROR", "TST", "NEG", "CMP", "CMN", "ORR",
+        "MUL", "BIC", "MVN",
+    ];
+    const COND: [&str; 14] = [
+        "EQ", "NE", "CS", "CC", "MI", "PL", "VS", "VC", "HI", "LS", "GE", "LT", "GT", "LE",
+    ];
+    let op = c.u16()?;
+    let pc = addr.wrapping_add(4);
+    let bits = |shift: u16, n: u16| (op >> shift) & ((1 << n) - 1);
+    let (rd, rs, rb) = (bits(0, 3), bits(3, 3), bits(6, 3));
+    let text = match op >> 11 {
+        0b00011 => {
+            let name = if bits(9, 1) == 0 { "ADD" } else { "SUB" };
+            match bits(10, 1) {
+                0 => format!("{} r{}, r{}, r{}", name, rd, rs, rb),
+                _ => format!("{} r{}, r{}, #{}", name, rd, rs, rb),
+            }
+        }
+        0b00000..=0b00010 => {
+            let name = ["LSL", "LSR", "ASR"][bits(11, 2) as usize];
+            format!("{} r{}, r{}, #{}", name, rd, rs, bits(6, 5))
+        }
+        0b00100..=0b00111 => {
+            let name = ["MOV", "CMP", "ADD", "SUB"][bits(11, 2) as usize];
+            format!("{} r{}, #{}", name, bits(8, 3), bits(0, 8))
+        }
+        0b01000 if bits(10, 1) == 0 => format!("{} r{}, r{}", ALU[bits(6, 4) as usize], rd, rs),
+        0b01000 => {
+            // high registers by their role
+            let reg = |r: u16| match r {
+                13 => "sp".to_string(),
+                14 => "lr".to_string(),
+                15 => "pc".to_string(),
+                r => format!("r{}", r),
+            };
+            let (rd, rs) = (reg(rd | (bits(7, 1) << 3)), reg(bits(3, 4)));
+            match bits(8, 2) {
+                0 => format!("ADD {}, {}", rd, rs),
+                1 => format!("CMP {}, {}", rd, rs),
+                2 => format!("MOV {}, {}", rd, rs),
+                _ => format!("{} {}", if bits(7, 1) == 0 { "BX" } else { "BLX" }, rs),
+            }
+        }
+        0b01001 => {
+            let target = (pc & !3) + bits(0, 8) as u32 * 4;
+            format!(
+                "LDR r{}, [pc, #{}] ; 0x{:08x}",
+                bits(8, 3),
+                bits(0, 8) * 4,
+                target
+            )
+        }
+        0b01010 | 0b01011 => {
+            let name = match (bits(9, 1), bits(10, 2)) {
+                (0, 0) => "STR",
+                (0, 1) => "STRB",
+                (0, 2) => "LDR",
+                (0, _) => "LDRB",
+                (_, 0) => "STRH",
+                (_, 1) => "LDSB",
+                (_, 2) => "LDRH",
+                _ => "LDSH",
+            };
+            format!("{} r{}, [r{}, r{}]", name, rd, rs, rb)
+        }
+        0b01100..=0b01111 => {
+            let name = ["STR", "LDR", "STRB", "LDRB"][bits(11, 2) as usize];
+            let scale = if bits(12, 1) == 0 { 4 } else { 1 };
+            format!("{} r{}, [r{}, #{}]", name, rd, rs, bits(6, 5) * scale)
+        }
+        0b10000 | 0b10001 => {
+            let name = if bits(11, 1) == 0 { "STRH" } else { "LDRH" };
+            format!("{} r{}, [r{}, #{}]", name, rd, rs, bits(6, 5) * 2)
+        }
+        0b10010 | 0b10011 => {
+            let name = if bits(11, 1) == 0 { "STR" } else { "LDR" };
+            format!("{} r{}, [sp, #{}]", name, bits(8, 3), bits(0, 8) * 4)
+        }
+        0b10100 | 0b10101 => {
+            let base = if bits(11, 1) == 0 { "pc" } else { "sp" };
+            format!("ADD r{}, {}, #{}", bits(8, 3), base, bits(0, 8) * 4)
+        }
+        0b10110 | 0b10111 if bits(8, 4) == 0 => {
+            let sign = if bits(7, 1) == 0 { "" } else { "-" };
+            format!("ADD sp, #{}{}", sign, bits(0, 7) * 4)
+        }
+        0b10110 | 0b10111 if bits(9, 2) == 0b10 => {
+            let (name, extra) = if bits(11, 1) == 0 {
+                ("PUSH", "lr")
+            } else {
+                ("POP", "pc")
+            };
+            let extra = (bits(8, 1) == 1).then_some(extra);
+            format!("{} {}", name, thumb_regs(bits(0, 8), extra))
+        }
+        0b11000 | 0b11001 => {
+            let name = if bits(11, 1) == 0 { "STMIA" } else { "LDMIA" };
+            format!(
+                "{} r{}!, {}",
+                name,
+                bits(8, 3),
+                thumb_regs(bits(0, 8), None)
+            )
+        }
+        0b11010 | 0b11011 => match bits(8, 4) {
+            0b1111 => format!("SWI #{}", bits(0, 8)),
+            0b1110 => return None,
+            cond => {
+                let target = pc.wrapping_add((bits(0, 8) as u8 as i8 as i32 * 2) as u32);
+                return Some((format!("B{} ", COND[cond as usize]), Some(target)));
+            }
+        },
+        0b11100 => {
+            let off = ((bits(0, 11) << 5) as i16 >> 4) as i32;
+            return Some(("B ".to_string(), Some(pc.wrapping_add(off as u32))));
+        }
+        0b11110 => {
+            // BL is a pair of halfwords holding the upper and lower part of the offset
+            let lo = c.u16()?;
+            if lo >> 11 != 0b11111 {
+                return None;
+            }
+            let hi = (((bits(0, 11) as u32) << 21) as i32 >> 9) as u32;
+            let target = pc.wrapping_add(hi).wrapping_add((lo as u32 & 0x7ff) << 1);
+            return Some(("BL ".to_string(), Some(target)));
+        }
+        _ => return None,
+    };
+    Some((text, None))
+}
+
+/// Decode the bytes loaded at `origin`, undecodable bytes become data directives.
+pub fn decode(cpu: Cpu, bytes: &[u8], origin: u32) -> Vec<Insn> {
+    let mut table = [None; 256];
+    for &(op, name, mode) in M6502_OPS {
+        table[op as usize] = Some((name, mode));
+    }
+    let mut res = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let addr = origin.wrapping_add(pos as u32);
+        let mut c = Cursor {
+            bytes,
+            start: pos,
+            pos,
+        };
+        let decoded = match cpu {
+            Cpu::M6502 => m6502(&mut c, addr, &table),
+            Cpu::Z80 | Cpu::Lr35902 => {
+                let gb = cpu == Cpu::Lr35902;
+                Z80 {
+                    c: &mut c,
+                    addr,
+                    gb,
+                    index: None,
+                }
+                .decode()
+            }
+            Cpu::Thumb => thumb(&mut c, addr),
+        };
+        let (text, target, len) = match decoded {
+            Some((text, target)) => (text, target, c.pos - pos),
+            None if cpu == Cpu::Thumb && pos + 1 < bytes.len() => {
+                let hw = u16::from_le_bytes([bytes[pos], bytes[pos + 1]]);
+                (format!(".hword 0x{:04x}", hw), None, 2)
+            }
+            None => (format!(".db ${:02X}", bytes[pos]), None, 1),
+        };
+        res.push(Insn {
+            offset: pos,
+            addr,
+            bytes: bytes[pos..pos + len].to_vec(),
+            text,
+            target,
+        });
+        pos += len;
+    }
+    res
+}
+
+/// Hex number with an optional `0x` or `$` prefix.
+pub fn parse_hex(s: &str) -> Option<usize> {
+    let s = s.trim();
+    let s = s
+        .strip_prefix("0x")
+        .or_else(|| s.strip_prefix('$'))
+        .unwrap_or(s);
+    usize::from_str_radix(s, 16).ok()
+}
+
+#[derive(Default)]
+pub struct Disassembly {
+    pub is_open: bool,
+    pub cpu: Cpu,
+    /// Byte offset in the input, hex.
+    pub start: String,
+    /// Number of bytes, hex, empty to the end of the input.
+    pub len: String,
+    /// Address the first byte is loaded at, hex.
+    pub origin: String,
+    pub lines: Vec<Insn>,
+    /// Names by byte offset in the input.
+    pub labels: HashMap<usize, String>,
+    /// Bytes in the input, jump targets past its end aren't linked.
+    pub input_len: usize,
+    /// State the lines were decoded for.
+    pub key: (String, Cpu, String, String, String, u64, usize, u64),
+}
+
+pub enum DisasmAction {
+    /// Show the byte offset of the input.
+    Goto(usize),
+    FromSelection,
+}
+
+impl Disassembly {
+    /// Offset of the range start and the address of it.
+    pub fn base(&self) -> (usize, u32) {
+        let start = parse_hex(&self.start).unwrap_or(0);
+        let origin = parse_hex(&self.origin).unwrap_or(0);
+        (start, origin as u32)
+    }
+
+    /// Input offset of an address, if it's loaded from the input.
+    fn offset_of(&self, addr: u32) -> Option<usize> {
+        let (start, origin) = self.base();
+        addr.checked_sub(origin)
+            .map(|d| start + d as usize)
+            .filter(|&o| o < self.input_len)
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui, theme: &Theme) -> Option<DisasmAction> {
+        let mut action = None;
+        ui.horizontal(|ui| {
+            ComboBox::from_id_salt("disasm_cpu")
+                .selected_text(self.cpu.name())
+                .show_ui(ui, |ui| {
+                    for cpu in Cpu::ALL {
+                        ui.selectable_value(&mut self.cpu, cpu, cpu.name());
+                    }
+                });
+            if ui.button("From selection").clicked() {
+                action = Some(DisasmAction::FromSelection);
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Offset");
+            ui.add(TextEdit::singleline(&mut self.start).desired_width(70.0));
+            ui.label("Length");
+            ui.add(TextEdit::singleline(&mut self.len).desired_width(70.0));
+            ui.label("Origin");
+            ui.add(TextEdit::singleline(&mut self.origin).desired_width(70.0));
+        });
+        ui.colored_label(
+            theme.comment,
+            "hex, the length defaults to the end of the input",
+        );
+        ui.separator();
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace) + 4.0;
+        let (start, _) = self.base();
+        ScrollArea::vertical()
+            .auto_shrink([false, true])
+            .max_height(400.0)
+            .show_rows(ui, row_height, self.lines.len(), |ui, rows| {
+                for insn in &self.lines[rows] {
+                    ui.horizontal(|ui| {
+                        if let Some(label) = self.labels.get(&(start + insn.offset)) {
+                            ui.colored_label(
+                                theme.selection,
+                                RichText::new(format!("{}:", label)).monospace(),
+                            );
+                        }
+                        let addr = self.cpu.fmt_addr(insn.addr);
+                        ui.colored_label(theme.comment, RichText::new(addr).monospace());
+                        let hex: Vec<String> =
+                            insn.bytes.iter().map(|b| format!("{:02X}", b)).collect();
+                        let hex = format!("{:<12}", hex.join(" "));
+                        ui.colored_label(theme.code_frozen, RichText::new(hex).monospace());
+                        ui.colored_label(theme.text, RichText::new(insn.text.as_str()).monospace());
+                        if let Some(target) = insn.target {
+                            let offset = self.offset_of(target);
+                            let name = offset
+                                .and_then(|o| self.labels.get(&o).cloned())
+                                .unwrap_or_else(|| self.cpu.fmt_addr(target));
+                            match offset {
+                                Some(o) => {
+                                    if ui.link(RichText::new(name).monospace()).clicked() {
+                                        action = Some(DisasmAction::Goto(o));
+                                    }
+                                }
+                                None => {
+                                    ui.colored_label(theme.text, RichText::new(name).monospace());
+                                }
+                            }
+                        }
+                    });
+                }
+            });
+        action
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insns(cpu: Cpu, bytes: &[u8], origin: u32) -> Vec<(u32, String, Option<u32>)> {
+        decode(cpu, bytes, origin)
+            .into_iter()
+            .map(|i| (i.addr, i.text, i.target))
+            .collect()
+    }
+
+    #[test]
+    fn m6502_relative_branches() {
+        assert_eq!(
+            insns(Cpu::M6502, &[0xd0, 0xfe, 0xf0, 0x02], 0x8000),
+            vec![
+                (0x8000, "BNE ".to_string(), Some(0x8000)),
+                (0x8002, "BEQ ".to_string(), Some(0x8006)),
+            ]
+        );
+        // the target wraps around the 16 bit address space
+        assert_eq!(insns(Cpu::M6502, &[0x10, 0x80], 0)[0].2, Some(0xff82));
+    }
+
+    #[test]
+    fn z80_indexed_bit_ops() {
+        assert_eq!(
+            insns(
+                Cpu::Z80,
+                &[0xdd, 0xcb, 0x05, 0x46, 0xfd, 0xcb, 0xfe, 0xc6],
+                0
+            ),
+            vec![
+                (0, "BIT 0,(IX+5)".to_string(), None),
+                (4, "SET 0,(IY-2)".to_string(), None),
+            ]
+        );
+        assert_eq!(
+            insns(Cpu::Z80, &[0x10, 0xfe], 0x100),
+            vec![(0x100, "DJNZ ".to_string(), Some(0x100))]
+        );
+    }
+
+    #[test]
+    fn lr35902_differs_from_z80() {
+        assert_eq!(
+            insns(Cpu::Lr35902, &[0xcb, 0x37, 0xe0, 0x44, 0xdd], 0x150),
+            vec![
+                (0x150, "SWAP A".to_string(), None),
+                (0x152, "LDH ($44),A".to_string(), None),
+                (0x154, ".db $DD".to_string(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn thumb_branches() {
+        // BL forward and backward, both halfwords make one instruction
+        assert_eq!(
+            insns(Cpu::Thumb, &[0x00, 0xf0, 0x7e, 0xf8], 0x0800_0000),
+            vec![(0x0800_0000, "BL ".to_string(), Some(0x0800_0100))]
+        );
+        assert_eq!(
+            insns(Cpu::Thumb, &[0xfe, 0xf7, 0xfe, 0xff], 0x0800_1000)[0].2,
+            Some(0x0800_0000)
+        );
+        assert_eq!(
+            insns(Cpu::Thumb, &[0xfe, 0xd0], 0x100),
+            vec![(0x100, "BEQ ".to_string(), Some(0x100))]
+        );
+        // the first half of BL without the second one is data
+        assert_eq!(
+            insns(Cpu::Thumb, &[0x00, 0xf0, 0x00, 0x20], 0)[0].1,
+            ".hword 0xf000"
+        );
+    }
+
+    #[test]
+    fn targets_past_the_input_are_not_offsets() {
+        let dis = Disassembly {
+            start: "10".to_string(),
+            origin: "8000".to_string(),
+            input_len: 0x20,
+            ..Default::default()
+        };
+        assert_eq!(dis.offset_of(0x800f), Some(0x1f));
+        assert_eq!(dis.offset_of(0x8010), None);
+        assert_eq!(dis.offset_of(0x7fff), None);
+    }
+}
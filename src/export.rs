@@ -0,0 +1,282 @@
+use crate::style::Theme;
+use crate::{binary, output};
+use egui::Ui;
+use serde_json::{Map, Value};
+use xeh::prelude::*;
+
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+    Yaml,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] = [ExportFormat::Json, ExportFormat::Csv, ExportFormat::Yaml];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "JSON",
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Yaml => "YAML",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Yaml => "yaml",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum BitstrEncoding {
+    #[default]
+    Hex,
+    Base64,
+}
+
+impl BitstrEncoding {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BitstrEncoding::Hex => "hex",
+            BitstrEncoding::Base64 => "base64",
+        }
+    }
+}
+
+/// Bytes as a string, a bitstr of a length not a multiple of 8 becomes
+/// `{"bits": n, "hex": ..}` with the last byte padded with zero bits.
+fn bitstr_value(bs: &Xbitstr, enc: BitstrEncoding) -> Value {
+    let len = bs.end() - bs.start();
+    if let Err(e) = binary::to_bytes_limited(bs) {
+        return Value::String(format!("<{}>", e));
+    }
+    let bytes = output::padded_bytes(bs);
+    let text = match enc {
+        BitstrEncoding::Hex => bytes.iter().map(|b| format!("{:02x}", b)).collect(),
+        BitstrEncoding::Base64 => binary::base64(&bytes),
+    };
+    if len.is_multiple_of(8) {
+        return Value::String(text);
+    }
+    let mut obj = Map::new();
+    obj.insert("bits".to_string(), Value::from(len));
+    obj.insert(enc.name().to_string(), Value::String(text));
+    Value::Object(obj)
+}
+
+/// Plain data of the cell, tagged values become `{"value": .., "tags": {..}}`.
+pub fn to_value(cell: &Cell, enc: BitstrEncoding) -> Value {
+    let value = if cell.is_nil() {
+        Value::Null
+    } else if let Ok(v) = cell.vec() {
+        Value::Array(v.iter().map(|c| to_value(c, enc)).collect())
+    } else if let Ok(m) = cell.xmap() {
+        Value::Object(to_map(m, enc))
+    } else if let Ok(bs) = cell.bitstr() {
+        bitstr_value(bs, enc)
+    } else if let Ok(s) = cell.str() {
+        Value::String(s.to_string())
+    } else if let Ok(i) = cell.to_xint() {
+        Value::from(i)
+    } else if let Ok(r) = cell.to_real() {
+        serde_json::Number::from_f64(r)
+            .map(Value::Number)
+            .unwrap_or(Value::Null)
+    } else {
+        Value::String(cell.format_safe())
+    };
+    match cell.tags() {
+        Some(tags) if tags.len() > 0 => {
+            let mut obj = Map::new();
+            obj.insert("value".to_string(), value);
+            obj.insert("tags".to_string(), Value::Object(to_map(tags, enc)));
+            Value::Object(obj)
+        }
+        _ => value,
+    }
+}
+
+fn to_map(m: &Xmap, enc: BitstrEncoding) -> Map<String, Value> {
+    m.iter()
+        .map(|(k, v)| {
+            let key = k
+                .str()
+                .map(|s| s.to_string())
+                .unwrap_or_else(|_| k.format_safe());
+            (key, to_value(v, enc))
+        })
+        .collect()
+}
+
+/// Variables as `{"name": .., "value": ..}` in the order of definition, the stack from bottom to top.
+pub fn results(vars: &[(Xstr, Cell)], stack: &[Cell], enc: BitstrEncoding) -> Value {
+    let vars = vars
+        .iter()
+        .map(|(name, val)| {
+            let mut var = Map::new();
+            var.insert("name".to_string(), Value::String(name.to_string()));
+            var.insert("value".to_string(), to_value(val, enc));
+            Value::Object(var)
+        })
+        .collect();
+    let stack = stack.iter().map(|val| to_value(val, enc)).collect();
+    let mut obj = Map::new();
+    obj.insert("vars".to_string(), Value::Array(vars));
+    obj.insert("stack".to_string(), Value::Array(stack));
+    Value::Object(obj)
+}
+
+pub fn render(format: ExportFormat, results: &Value) -> Result<String, String> {
+    match format {
+        ExportFormat::Json => serde_json::to_string_pretty(results).map_err(|e| e.to_string()),
+        ExportFormat::Yaml => {
+            let mut out = String::new();
+            let mut emitter = yaml_rust::YamlEmitter::new(&mut out);
+            emitter
+                .dump(&to_yaml(results))
+                .map_err(|e| format!("{:?}", e))?;
+            out.push('\n');
+            Ok(out)
+        }
+        ExportFormat::Csv => csv(results),
+    }
+}
+
+fn to_yaml(v: &Value) -> yaml_rust::Yaml {
+    use yaml_rust::Yaml;
+    match v {
+        Value::Null => Yaml::Null,
+        Value::Bool(b) => Yaml::Boolean(*b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => Yaml::Integer(i),
+            None => Yaml::Real(n.to_string()),
+        },
+        Value::String(s) => Yaml::String(s.clone()),
+        Value::Array(a) => Yaml::Array(a.iter().map(to_yaml).collect()),
+        Value::Object(m) => Yaml::Hash(
+            m.iter()
+                .map(|(k, v)| (Yaml::String(k.clone()), to_yaml(v)))
+                .collect(),
+        ),
+    }
+}
+
+fn is_container(v: &Value) -> bool {
+    match v {
+        Value::Array(a) => !a.is_empty(),
+        Value::Object(m) => !m.is_empty(),
+        _ => false,
+    }
+}
+
+fn csv_field(v: &Value) -> String {
+    let s = match v {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        _ => v.to_string(),
+    };
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s
+    }
+}
+
+/// One column per vector of scalars, the other results are left out.
+fn csv(results: &Value) -> Result<String, String> {
+    let untag = |v: &Value| match v.get("value") {
+        Some(inner) if v.get("tags").is_some() => inner.clone(),
+        _ => v.clone(),
+    };
+    let mut roots: Vec<(String, Value)> = Vec::new();
+    if let Some(vars) = results["vars"].as_array() {
+        roots.extend(vars.iter().map(|v| {
+            let name = v["name"].as_str().unwrap_or_default().to_string();
+            (name, untag(&v["value"]))
+        }));
+    }
+    if let Some(stack) = results["stack"].as_array() {
+        let n = stack.len();
+        roots.extend(
+            stack
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (format!("stack {}", n - 1 - i), untag(v))),
+        );
+    }
+    let columns: Vec<(String, Vec<Value>)> = roots
+        .into_iter()
+        .filter_map(|(name, v)| {
+            let items: Vec<Value> = v.as_array()?.iter().map(untag).collect();
+            (!items.iter().any(is_container)).then_some((name, items))
+        })
+        .collect();
+    if columns.is_empty() {
+        return Err("CSV export needs a variable or stack item holding a flat vector".to_string());
+    }
+    let rows = columns
+        .iter()
+        .map(|(_, items)| items.len())
+        .max()
+        .unwrap_or(0);
+    let mut out = columns
+        .iter()
+        .map(|(name, _)| csv_field(&Value::String(name.clone())))
+        .collect::<Vec<_>>()
+        .join(",");
+    out.push('\n');
+    for i in 0..rows {
+        let row: Vec<String> = columns
+            .iter()
+            .map(|(_, items)| items.get(i).map(csv_field).unwrap_or_default())
+            .collect();
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+#[derive(Default)]
+pub struct Export {
+    pub is_open: bool,
+    pub format: ExportFormat,
+    pub encoding: BitstrEncoding,
+}
+
+pub enum ExportAction {
+    Copy,
+    Save,
+}
+
+impl Export {
+    pub fn ui(&mut self, ui: &mut Ui, theme: &Theme) -> Option<ExportAction> {
+        let mut action = None;
+        ui.horizontal(|ui| {
+            for f in ExportFormat::ALL {
+                ui.selectable_value(&mut self.format, f, f.name());
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.colored_label(theme.comment, "bitstr as");
+            ui.selectable_value(&mut self.encoding, BitstrEncoding::Hex, "hex");
+            ui.selectable_value(&mut self.encoding, BitstrEncoding::Base64, "base64");
+        });
+        if self.format == ExportFormat::Csv {
+            ui.colored_label(theme.comment, "Flat vectors only, one column each");
+        }
+        ui.horizontal(|ui| {
+            if ui.button("📋 Copy").clicked() {
+                action = Some(ExportAction::Copy);
+            }
+            if ui.button("💾 Save...").clicked() {
+                action = Some(ExportAction::Save);
+            }
+        });
+        action
+    }
+}
@@ -0,0 +1,292 @@
+use crate::search::{self, Pattern, SearchMode};
+use crate::style::Theme;
+use egui::{RichText, ScrollArea, Ui};
+use std::borrow::Cow;
+use xeh::prelude::*;
+
+/// Bytes at the start of the input looked at by the detection.
+const MAX_MAGIC_END: usize = 64 * 1024;
+
+/// Bytes expected at the offset, `None` matches any byte.
+pub struct Signature {
+    pub offset: usize,
+    pub bytes: Vec<Option<u8>>,
+}
+
+impl Signature {
+    /// Parse `offset hex-pattern`, e.g. `0 50 4B ?? 04`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        let (offset, pattern) = text
+            .split_once(char::is_whitespace)
+            .ok_or("expected offset and bytes")?;
+        let offset = match offset.strip_prefix("0x") {
+            Some(hex) => usize::from_str_radix(hex, 16),
+            None => offset.parse(),
+        }
+        .map_err(|e| format!("bad offset {}: {}", offset, e))?;
+        match search::parse_pattern(SearchMode::Hex, pattern)? {
+            Pattern::Bytes(bytes) if offset + bytes.len() <= MAX_MAGIC_END => {
+                Ok(Self { offset, bytes })
+            }
+            _ => Err(format!("signature must end within {} bytes", MAX_MAGIC_END)),
+        }
+    }
+
+    fn end(&self) -> usize {
+        self.offset + self.bytes.len()
+    }
+
+    fn matches(&self, head: &[u8]) -> bool {
+        match head.get(self.offset..self.end()) {
+            Some(data) => data
+                .iter()
+                .zip(&self.bytes)
+                .all(|(b, p)| p.is_none_or(|p| p == *b)),
+            None => false,
+        }
+    }
+}
+
+/// Parser script, offered for inputs starting with one of its signatures.
+pub struct Format {
+    pub name: String,
+    pub signatures: Vec<Signature>,
+    pub source: Cow<'static, str>,
+    /// Sample input of the examples menu, empty for scripts that make their own data.
+    pub example: Option<&'static [u8]>,
+    pub builtin: bool,
+}
+
+impl Format {
+    /// Read the leading `\ name:` and `\ magic:` comments of the script.
+    pub fn new(default_name: &str, source: Cow<'static, str>) -> Result<Self, String> {
+        let mut name = default_name.to_string();
+        let mut signatures = Vec::new();
+        for line in source
+            .lines()
+            .map(str::trim)
+            .take_while(|l| l.is_empty() || l.starts_with('\\'))
+        {
+            let line = line.trim_start_matches('\\').trim();
+            if let Some(n) = line.strip_prefix("name:") {
+                name = n.trim().to_string();
+            } else if let Some(magic) = line.strip_prefix("magic:") {
+                signatures.push(Signature::parse(magic)?);
+            }
+        }
+        Ok(Self {
+            name,
+            signatures,
+            source,
+            example: None,
+            builtin: false,
+        })
+    }
+
+    fn builtin(name: &str, source: &'static str, example: Option<&'static [u8]>) -> Self {
+        let mut f = Self::new(name, Cow::Borrowed(source)).expect("bad builtin format header");
+        f.example = example;
+        f.builtin = true;
+        f
+    }
+
+    pub fn matches(&self, head: &[u8]) -> bool {
+        self.signatures.iter().any(|s| s.matches(head))
+    }
+}
+
+pub struct Library {
+    pub formats: Vec<Format>,
+}
+
+impl Default for Library {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl Library {
+    pub fn builtin() -> Self {
+        let ex = |bin: &'static [u8]| Some(bin);
+        let formats = vec![
+            Format::builtin(
+                "C String",
+                include_str!("../assets/examples/cstring.xeh"),
+                ex(include_bytes!("../assets/examples/cstring.bin")),
+            ),
+            Format::builtin(
+                "Doom Fire",
+                include_str!("../assets/examples/doom-fire.xeh"),
+                ex(&[]),
+            ),
+            Format::builtin(
+                "Gameboy Tile 2BPP",
+                include_str!("../assets/examples/gb-tile-2bpp.xeh"),
+                ex(include_bytes!("../assets/examples/gb-tile-2bpp.bin")),
+            ),
+            Format::builtin(
+                "iNES ROM",
+                include_str!("../assets/examples/ines.xeh"),
+                ex(include_bytes!("../assets/examples/ines.bin")),
+            ),
+            Format::builtin(
+                "Quake1Pak",
+                include_str!("../assets/examples/quake-pak.xeh"),
+                ex(include_bytes!("../assets/examples/quake-pak.bin")),
+            ),
+            Format::builtin(
+                "Quake1Pak Build",
+                include_str!("../assets/examples/quake-pak-build.xeh"),
+                ex(&[]),
+            ),
+            Format::builtin(
+                "VLQ Integer",
+                include_str!("../assets/examples/vint.xeh"),
+                ex(include_bytes!("../assets/examples/vint.bin")),
+            ),
+            Format::builtin("PNG image", include_str!("../assets/formats/png.xeh"), None),
+            Format::builtin(
+                "ZIP archive",
+                include_str!("../assets/formats/zip.xeh"),
+                None,
+            ),
+            Format::builtin(
+                "ELF executable",
+                include_str!("../assets/formats/elf.xeh"),
+                None,
+            ),
+            Format::builtin(
+                "PE executable",
+                include_str!("../assets/formats/pe.xeh"),
+                None,
+            ),
+            Format::builtin("BMP image", include_str!("../assets/formats/bmp.xeh"), None),
+            Format::builtin("WAV audio", include_str!("../assets/formats/wav.xeh"), None),
+            Format::builtin(
+                "GZIP stream",
+                include_str!("../assets/formats/gzip.xeh"),
+                None,
+            ),
+        ];
+        Self { formats }
+    }
+
+    /// Replace the user formats with the `.xeh` scripts of the directory, return the errors.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_dir(&mut self, dir: &std::path::Path) -> Vec<String> {
+        self.formats.retain(|f| f.builtin);
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => return vec![format!("{:?}: {}", dir, e)],
+        };
+        let mut paths: Vec<_> = entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == "xeh"))
+            .collect();
+        paths.sort();
+        let mut errors = Vec::new();
+        for path in paths {
+            let stem = path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            let res = std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|src| Format::new(&stem, Cow::Owned(src)));
+            match res {
+                Ok(f) => self.formats.push(f),
+                Err(e) => errors.push(format!("{:?}: {}", path, e)),
+            }
+        }
+        errors
+    }
+
+    /// Indices of the formats whose signature is found in the input.
+    pub fn detect(&self, bs: &Xbitstr) -> Vec<usize> {
+        let end = self
+            .formats
+            .iter()
+            .flat_map(|f| f.signatures.iter().map(Signature::end))
+            .max();
+        let head: Vec<u8> = bs.iter8().take(end.unwrap_or(0)).map(|(b, _)| b).collect();
+        (0..self.formats.len())
+            .filter(|&i| self.formats[i].matches(&head))
+            .collect()
+    }
+}
+
+#[derive(Default)]
+pub struct FormatsPanel {
+    pub is_open: bool,
+    /// Formats matching the last opened input.
+    pub detected: Vec<usize>,
+    pub user_dir: String,
+    pub errors: Vec<String>,
+}
+
+pub enum FormatAction {
+    /// Open the parser source in a new buffer.
+    Open(usize),
+    #[cfg(not(target_arch = "wasm32"))]
+    Reload,
+}
+
+impl FormatsPanel {
+    pub fn ui(&mut self, ui: &mut Ui, theme: &Theme, lib: &Library) -> Option<FormatAction> {
+        let mut action = None;
+        if !self.detected.is_empty() {
+            ui.colored_label(theme.text, "Detected in the input:");
+            ui.horizontal_wrapped(|ui| {
+                for &i in &self.detected {
+                    if ui.button(lib.formats[i].name.as_str()).clicked() {
+                        action = Some(FormatAction::Open(i));
+                    }
+                }
+            });
+            ui.separator();
+        }
+        ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+            for (i, f) in lib
+                .formats
+                .iter()
+                .enumerate()
+                .filter(|(_, f)| !f.signatures.is_empty())
+            {
+                ui.horizontal(|ui| {
+                    if ui
+                        .selectable_label(self.detected.contains(&i), f.name.as_str())
+                        .clicked()
+                    {
+                        action = Some(FormatAction::Open(i));
+                    }
+                    let magic = f.signatures.iter().map(|s| {
+                        let bytes = s
+                            .bytes
+                            .iter()
+                            .map(|b| b.map_or("??".to_string(), |b| format!("{:02X}", b)));
+                        format!("@{} {}", s.offset, bytes.collect::<Vec<_>>().join(" "))
+                    });
+                    let text = RichText::new(magic.collect::<Vec<_>>().join(", ")).monospace();
+                    ui.colored_label(theme.comment, text);
+                });
+            }
+        });
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("Parsers directory");
+                ui.text_edit_singleline(&mut self.user_dir);
+                if ui.button("Reload").clicked() {
+                    action = Some(FormatAction::Reload);
+                }
+            });
+        }
+        for e in &self.errors {
+            ui.colored_label(theme.error, e.as_str());
+        }
+        action
+    }
+}
@@ -0,0 +1,227 @@
+use crate::binary;
+use crate::reads::InputId;
+use crate::style::Theme;
+use egui::{Grid, RichText, Ui};
+use sha2::Digest;
+use xeh::prelude::*;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Algo {
+    Crc32,
+    Adler32,
+    Md5,
+    Sha1,
+    Sha256,
+    Sum8,
+    Sum16,
+    Sum32,
+}
+
+impl Algo {
+    pub const ALL: [Algo; 8] = [
+        Algo::Crc32,
+        Algo::Adler32,
+        Algo::Md5,
+        Algo::Sha1,
+        Algo::Sha256,
+        Algo::Sum8,
+        Algo::Sum16,
+        Algo::Sum32,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Algo::Crc32 => "crc32",
+            Algo::Adler32 => "adler32",
+            Algo::Md5 => "md5",
+            Algo::Sha1 => "sha1",
+            Algo::Sha256 => "sha256",
+            Algo::Sum8 => "sum8",
+            Algo::Sum16 => "sum16",
+            Algo::Sum32 => "sum32",
+        }
+    }
+}
+
+/// Checksums are numbers, digests are bytes.
+pub enum Hash {
+    Int(u32),
+    Digest(Vec<u8>),
+}
+
+impl Hash {
+    pub fn to_hex(&self) -> String {
+        match self {
+            Hash::Int(n) => format!("{:08x}", n),
+            Hash::Digest(d) => d.iter().map(|b| format!("{:02x}", b)).collect(),
+        }
+    }
+
+    fn to_cell(&self) -> Cell {
+        match self {
+            Hash::Int(n) => Cell::from(*n as Xint),
+            Hash::Digest(d) => Cell::from(Xbitstr::from(d.clone())),
+        }
+    }
+}
+
+pub fn compute(algo: Algo, data: &[u8]) -> Hash {
+    let sum = || data.iter().fold(0u32, |acc, &b| acc.wrapping_add(b as u32));
+    match algo {
+        Algo::Crc32 => Hash::Int(crc32fast::hash(data)),
+        Algo::Adler32 => Hash::Int(adler2::adler32_slice(data)),
+        Algo::Md5 => Hash::Digest(md5::Md5::digest(data).to_vec()),
+        Algo::Sha1 => Hash::Digest(sha1::Sha1::digest(data).to_vec()),
+        Algo::Sha256 => Hash::Digest(sha2::Sha256::digest(data).to_vec()),
+        Algo::Sum8 => Hash::Int(sum() & 0xff),
+        Algo::Sum16 => Hash::Int(sum() & 0xffff),
+        Algo::Sum32 => Hash::Int(sum()),
+    }
+}
+
+fn hash_word(xs: &mut Xstate, algo: Algo) -> Xresult {
+    let bs = xs.pop_data()?;
+    let bytes = binary::to_bytes(bs.bitstr()?);
+    xs.push_data(compute(algo, &bytes).to_cell())
+}
+
+fn hash_crc32(xs: &mut Xstate) -> Xresult {
+    hash_word(xs, Algo::Crc32)
+}
+
+fn hash_adler32(xs: &mut Xstate) -> Xresult {
+    hash_word(xs, Algo::Adler32)
+}
+
+fn hash_md5(xs: &mut Xstate) -> Xresult {
+    hash_word(xs, Algo::Md5)
+}
+
+fn hash_sha1(xs: &mut Xstate) -> Xresult {
+    hash_word(xs, Algo::Sha1)
+}
+
+fn hash_sha256(xs: &mut Xstate) -> Xresult {
+    hash_word(xs, Algo::Sha256)
+}
+
+fn hash_sum8(xs: &mut Xstate) -> Xresult {
+    hash_word(xs, Algo::Sum8)
+}
+
+fn hash_sum16(xs: &mut Xstate) -> Xresult {
+    hash_word(xs, Algo::Sum16)
+}
+
+fn hash_sum32(xs: &mut Xstate) -> Xresult {
+    hash_word(xs, Algo::Sum32)
+}
+
+/// Hex of an int checksum or a digest, for the mismatch message.
+fn fmt_hash(cell: &Cell) -> String {
+    if let Ok(n) = cell.to_xint() {
+        format!("{:#010x}", n)
+    } else if let Ok(bs) = cell.bitstr() {
+        let bytes = binary::to_bytes(bs);
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    } else {
+        cell.format_safe()
+    }
+}
+
+/// `stored computed hash/assert` fails unless both are the same.
+fn hash_assert(xs: &mut Xstate) -> Xresult {
+    let computed = xs.pop_data()?;
+    let stored = xs.pop_data()?;
+    let same = match (stored.to_xint(), computed.to_xint()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => match (stored.bitstr(), computed.bitstr()) {
+            (Ok(a), Ok(b)) => binary::to_bytes(a) == binary::to_bytes(b),
+            _ => stored == computed,
+        },
+    };
+    if same {
+        return Ok(());
+    }
+    let msg = format!(
+        "checksum mismatch: stored {}, computed {}",
+        fmt_hash(&stored),
+        fmt_hash(&computed)
+    );
+    Err(Xerr::ErrorMsg(Xstr::from(msg)))
+}
+
+/// `bitstr hash/crc32 -- int`, the same for the other algorithms, digests are bitstrs.
+pub fn install(xs: &mut Xstate) -> Xresult {
+    xs.defword("hash/crc32", hash_crc32)?;
+    xs.defword("hash/adler32", hash_adler32)?;
+    xs.defword("hash/md5", hash_md5)?;
+    xs.defword("hash/sha1", hash_sha1)?;
+    xs.defword("hash/sha256", hash_sha256)?;
+    xs.defword("hash/sum8", hash_sum8)?;
+    xs.defword("hash/sum16", hash_sum16)?;
+    xs.defword("hash/sum32", hash_sum32)?;
+    xs.defword("hash/assert", hash_assert)?;
+    Ok(())
+}
+
+#[derive(Default)]
+pub struct HashPanel {
+    pub is_open: bool,
+    /// Hash the whole input rather than the selection.
+    pub whole_input: bool,
+    pub results: Vec<(Algo, String)>,
+    pub error: Option<String>,
+    /// Number of bytes hashed.
+    pub len: usize,
+    /// Input, selection and scope the results were computed for.
+    pub key: (InputId, Option<std::ops::Range<usize>>, bool),
+}
+
+impl HashPanel {
+    /// Hash again, the bytes of the input have changed.
+    pub fn invalidate(&mut self) {
+        self.key = Default::default();
+    }
+
+    pub fn update(&mut self, data: Result<Vec<u8>, String>) {
+        self.results.clear();
+        self.len = 0;
+        self.error = None;
+        match data {
+            Ok(data) => {
+                self.len = data.len();
+                self.results = Algo::ALL
+                    .iter()
+                    .map(|&a| (a, compute(a, &data).to_hex()))
+                    .collect();
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui, theme: &Theme) {
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut self.whole_input, false, "Selection");
+            ui.selectable_value(&mut self.whole_input, true, "Whole input");
+            ui.colored_label(theme.comment, format!("{} bytes", self.len));
+        });
+        if let Some(e) = &self.error {
+            ui.colored_label(theme.error, e);
+        }
+        Grid::new("hash_grid").striped(true).show(ui, |ui| {
+            for (algo, hex) in &self.results {
+                ui.colored_label(theme.text, algo.name());
+                ui.label(
+                    RichText::new(hex.as_str())
+                        .monospace()
+                        .color(theme.code_frozen),
+                );
+                if ui.small_button("📋").on_hover_text("Copy").clicked() {
+                    ui.ctx().copy_text(hex.clone());
+                }
+                ui.end_row();
+            }
+        });
+    }
+}
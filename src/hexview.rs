@@ -0,0 +1,45 @@
+use crate::bitview::BitView;
+use egui::{ComboBox, Ui};
+
+/// Column counts offered in the panel, 0 fits the columns to the panel width.
+const COLUMN_CHOICES: [usize; 4] = [0, 8, 16, 32];
+
+/// Characters of a hex row besides the columns, the address and the gap before ASCII.
+pub const ROW_EXTRA_CHARS: usize = 10;
+
+/// Persisted layout of the hex panel.
+#[cfg_attr(feature = "persistence", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "persistence", serde(default))]
+#[derive(Clone, Copy, PartialEq, Default)]
+pub struct HexLayout {
+    /// Number of byte columns, 0 fits them to the panel width.
+    pub columns: usize,
+    pub bit_view: BitView,
+}
+
+impl HexLayout {
+    /// Columns to show in a panel `width_chars` characters wide.
+    pub fn num_cols(&self, width_chars: usize) -> usize {
+        if self.columns > 0 {
+            self.columns
+        } else {
+            (width_chars.saturating_sub(ROW_EXTRA_CHARS) / 4).max(1)
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            self.bit_view.ui(ui);
+            let label = |n: usize| if n == 0 { "Fit".to_string() } else { n.to_string() };
+            ComboBox::from_id_salt("hex_columns")
+                .width(50.0)
+                .selected_text(label(self.columns))
+                .show_ui(ui, |ui| {
+                    for n in COLUMN_CHOICES {
+                        ui.selectable_value(&mut self.columns, n, label(n));
+                    }
+                });
+            ui.label("cols");
+        });
+    }
+}
@@ -1,23 +1,48 @@
 use eframe::{egui, egui::*};
 
+/// Single letter hotkeys must not fire on Ctrl/Cmd shortcuts.
+fn no_command(i: &InputState) -> bool {
+    !i.modifiers.command && !i.modifiers.ctrl
+}
+
 pub fn interactive_canvas_pressed(i: &InputState) -> bool {
-    i.key_pressed(Key::M)
+    no_command(i) && i.key_pressed(Key::M)
 }
 
 pub fn focus_on_code_pressed(i: &InputState) -> bool {
-    i.key_pressed(egui::Key::E)
+    no_command(i) && i.key_pressed(egui::Key::E)
 }
 
 pub fn file_open_pressed(i: &InputState) -> bool {
-    i.key_pressed(egui::Key::O)
+    no_command(i) && i.key_pressed(egui::Key::O)
 }
 
 pub fn goto_pressed(i: &InputState) -> bool {
-    i.key_pressed(egui::Key::G)
+    no_command(i) && i.key_pressed(egui::Key::G)
+}
+
+pub fn find_pressed(i: &InputState) -> bool {
+    no_command(i) && i.key_pressed(egui::Key::F)
+}
+
+pub fn find_next_pressed(i: &InputState) -> bool {
+    !i.modifiers.shift && i.key_pressed(egui::Key::F3)
+}
+
+pub fn find_prev_pressed(i: &InputState) -> bool {
+    i.modifiers.shift && i.key_pressed(egui::Key::F3)
+}
+
+pub fn next_diff_pressed(i: &InputState) -> bool {
+    !i.modifiers.shift && i.key_pressed(egui::Key::F8)
+}
+
+pub fn prev_diff_pressed(i: &InputState) -> bool {
+    i.modifiers.shift && i.key_pressed(egui::Key::F8)
 }
 
 pub fn help_pressed(i: &InputState) -> bool {
-    i.key_pressed(egui::Key::H)
+    no_command(i) && i.key_pressed(egui::Key::H)
 }
 
 pub fn scroll_view_pressed(ctx: &egui::Context, page_size: isize) -> isize {
@@ -35,27 +60,27 @@ pub fn scroll_view_pressed(ctx: &egui::Context, page_size: isize) -> isize {
 }
 
 pub fn recording_pressed(i: &InputState) -> bool {
-    i.key_pressed(egui::Key::Y)
+    no_command(i) && i.key_pressed(egui::Key::Y)
 }
 
 pub fn snapshot_pressed(i: &InputState) -> bool {
-    i.key_pressed(egui::Key::S)
+    no_command(i) && i.key_pressed(egui::Key::S)
 }
 
 pub fn rollback_pressed(i: &InputState) -> bool {
-    i.key_pressed(egui::Key::L)
+    no_command(i) && i.key_pressed(egui::Key::L)
 }
 
 pub fn next_pressed(i: &InputState) -> bool {
-    i.key_pressed(egui::Key::N)
+    no_command(i) && i.key_pressed(egui::Key::N)
 }
 
 pub fn rnext_pressed(i: &InputState) -> bool {
-    i.key_pressed(egui::Key::B)
+    no_command(i) && i.key_pressed(egui::Key::B)
 }
 
 pub fn run_pressed(i: &InputState) -> bool {
-    i.key_pressed(egui::Key::R)
+    no_command(i) && i.key_pressed(egui::Key::R)
 }
 
 pub fn command_palette_pressed(i: &InputState) -> bool {
@@ -66,3 +91,16 @@ pub fn command_palette_pressed(i: &InputState) -> bool {
     };
     modifiers && i.modifiers.shift && i.key_pressed(egui::Key::P)
 }
+
+pub fn completion_pressed(i: &InputState) -> bool {
+    i.modifiers.ctrl && i.key_pressed(egui::Key::Space)
+}
+
+pub fn undo_pressed(i: &InputState) -> bool {
+    i.modifiers.command && !i.modifiers.shift && i.key_pressed(egui::Key::Z)
+}
+
+pub fn redo_pressed(i: &InputState) -> bool {
+    i.modifiers.command
+        && (i.key_pressed(egui::Key::Y) || (i.modifiers.shift && i.key_pressed(egui::Key::Z)))
+}
@@ -0,0 +1,173 @@
+use crate::{binary, bookmarks};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::ops::Range;
+use xeh::prelude::*;
+
+/// Inputs without a file are kept in the workspace up to this size.
+const MAX_SAVED_BYTES: usize = 1024 * 1024;
+
+/// Binary loaded into the session, scripts reach it by name.
+#[derive(Clone)]
+pub struct Input {
+    pub name: String,
+    pub data: Xbitstr,
+    /// Content hash, the key of the input bookmarks.
+    pub hash: String,
+    /// File the input was opened from, reopened when the workspace is loaded.
+    pub path: Option<String>,
+    pub parent: Option<Parent>,
+    /// Bytes patched since the file was opened, offset to the original and the new value.
+    pub patches: BTreeMap<usize, (u8, u8)>,
+}
+
+/// Region of another input the data was decompressed from.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Parent {
+    pub name: String,
+    /// Bits of the parent input.
+    pub range: Range<usize>,
+    pub codec: String,
+}
+
+impl Input {
+    pub fn new(name: &str, data: Xbitstr, path: Option<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            hash: bookmarks::input_hash(&data),
+            data,
+            path,
+            parent: None,
+            patches: BTreeMap::new(),
+        }
+    }
+
+    /// Replace the bytes keeping track of what differs from the file.
+    pub fn patch(&mut self, bytes: Vec<u8>) {
+        for (i, ((old, _), &new)) in self.data.iter8().zip(&bytes).enumerate() {
+            if old == new {
+                continue;
+            }
+            let orig = self.patches.get(&i).map_or(old, |p| p.0);
+            if orig == new {
+                self.patches.remove(&i);
+            } else {
+                self.patches.insert(i, (orig, new));
+            }
+        }
+        self.data = Xbitstr::from(bytes);
+    }
+
+    pub fn saved(&self) -> SavedInput {
+        let len = (self.data.end() - self.data.start()).div_ceil(8);
+        // the original bytes keep the hash the bookmarks are stored under
+        let data = (self.path.is_none() && len <= MAX_SAVED_BYTES).then(|| {
+            let mut bytes = binary::to_bytes(&self.data);
+            for (&i, &(orig, _)) in &self.patches {
+                bytes[i] = orig;
+            }
+            binary::base64(&bytes)
+        });
+        let patches = self.patches.iter().map(|(&i, &(_, b))| (i, b)).collect();
+        SavedInput {
+            name: self.name.clone(),
+            path: self.path.clone(),
+            data,
+            parent: self.parent.clone(),
+            patches,
+        }
+    }
+}
+
+/// Input as stored in the workspace, by path or by base64 encoded content.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SavedInput {
+    pub name: String,
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub data: Option<String>,
+    #[serde(default)]
+    pub parent: Option<Parent>,
+    /// Bytes patched since the input was opened.
+    #[serde(default)]
+    pub patches: Vec<(usize, u8)>,
+}
+
+impl SavedInput {
+    pub fn restore(&self) -> Result<Input, String> {
+        if let Some(data) = &self.data {
+            let bytes = binary::from_base64(data).ok_or("bad base64 data")?;
+            let mut input = Input::new(&self.name, Xbitstr::from(bytes), None);
+            input.parent = self.parent.clone();
+            self.apply_patches(&mut input)?;
+            return Ok(input);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(path) = &self.path {
+            let data = load_path(std::path::Path::new(path)).map_err(|e| e.to_string())?;
+            let mut input = Input::new(&self.name, data, Some(path.clone()));
+            self.apply_patches(&mut input)?;
+            return Ok(input);
+        }
+        Err("no data saved".to_string())
+    }
+
+    fn apply_patches(&self, input: &mut Input) -> Result<(), String> {
+        if self.patches.is_empty() {
+            return Ok(());
+        }
+        let mut bytes = binary::to_bytes_limited(&input.data)?;
+        for &(i, b) in &self.patches {
+            *bytes.get_mut(i).ok_or("patch past the end of the input")? = b;
+        }
+        input.patch(bytes);
+        Ok(())
+    }
+}
+
+/// Load the file through xeh, with the `mmap` feature it's mapped rather than read.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_path(path: &std::path::Path) -> Xresult1<Xbitstr> {
+    let mut xs = Xstate::core()?;
+    xeh::file::fs_overlay::load_binary(&mut xs, &path.to_string_lossy())?;
+    Ok(xs.eval_named_value("input")?.bitstr()?.clone())
+}
+
+thread_local! {
+    static INPUTS: RefCell<Vec<(String, Xbitstr)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Make the inputs visible to the script words.
+pub fn publish(inputs: &[Input]) {
+    let list = inputs.iter().map(|i| (i.name.clone(), i.data.clone())).collect();
+    INPUTS.with(|r| *r.borrow_mut() = list);
+}
+
+fn find(xs: &mut Xstate) -> Xresult1<Xbitstr> {
+    let name = xs.pop_data()?;
+    let name = name.str()?;
+    let found = INPUTS.with(|r| {
+        let list = r.borrow();
+        list.iter().find(|(n, _)| n == name).map(|(_, bs)| bs.clone())
+    });
+    found.ok_or_else(|| Xerr::ErrorMsg(Xstr::from(format!("no input named {}", name))))
+}
+
+fn select_input(xs: &mut Xstate) -> Xresult {
+    let bs = find(xs)?;
+    xs.set_binary_input(bs)
+}
+
+fn named_input(xs: &mut Xstate) -> Xresult {
+    let bs = find(xs)?;
+    xs.push_data(Cell::from(bs))
+}
+
+/// `"name" input/select` makes the input current, `"name" input/named` leaves it on the stack.
+pub fn install(xs: &mut Xstate) -> Xresult {
+    xs.defword("input/select", select_input)?;
+    xs.defword("input/named", named_input)?;
+    Ok(())
+}
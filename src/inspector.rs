@@ -0,0 +1,187 @@
+use crate::style::Theme;
+use egui::{DragValue, Grid, RichText, Sense, Ui};
+
+const VLQ_WORD: &str = ": decode-vlq
+    0
+    begin
+        7 lshift
+        u1 read
+        swap
+        u7 read or
+        swap zero?
+    until
+;
+";
+
+pub struct Interpretation {
+    pub name: &'static str,
+    pub value: String,
+    /// Read template, `None` means the value needs a helper word.
+    pub template: Option<&'static str>,
+}
+
+/// Bytes needed to show every interpretation.
+pub const MAX_BYTES: usize = 16;
+
+fn int<const N: usize>(bytes: &[u8], be: bool) -> Option<[u8; N]> {
+    let mut buf: [u8; N] = bytes.get(..N)?.try_into().ok()?;
+    if !be {
+        buf.reverse();
+    }
+    Some(buf)
+}
+
+fn fmt_int<const N: usize>(bytes: &[u8], be: bool, signed: bool) -> Option<String> {
+    let buf = int::<N>(bytes, be)?;
+    let val = buf.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+    if signed {
+        let shift = 64 - N * 8;
+        Some(format!("{}", ((val << shift) as i64) >> shift))
+    } else {
+        Some(format!("{}", val))
+    }
+}
+
+/// Variable length quantity, 7 bits per byte, high bit set on all but the last byte.
+pub fn decode_vlq(bytes: &[u8]) -> Option<(u64, usize)> {
+    let mut val = 0u64;
+    for (i, &b) in bytes.iter().enumerate().take(10) {
+        val = (val << 7) | (b & 0x7f) as u64;
+        if b & 0x80 == 0 {
+            return Some((val, i + 1));
+        }
+    }
+    None
+}
+
+/// Format seconds since the Unix epoch as an UTC date.
+pub fn fmt_unix_time(secs: i64) -> String {
+    let days = secs.div_euclid(86400);
+    let rem = secs.rem_euclid(86400);
+    // days to civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        y,
+        m,
+        d,
+        rem / 3600,
+        rem / 60 % 60,
+        rem % 60
+    )
+}
+
+/// Microsoft GUID layout, the first three groups are little endian.
+pub fn fmt_guid(b: &[u8]) -> Option<String> {
+    let b = b.get(..16)?;
+    Some(format!(
+        "{{{:02x}{:02x}{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}}}",
+        b[3], b[2], b[1], b[0], b[5], b[4], b[7], b[6],
+        b[8], b[9], b[10], b[11], b[12], b[13], b[14], b[15]
+    ))
+}
+
+pub fn interpret(bytes: &[u8]) -> Vec<Interpretation> {
+    let mut res = Vec::new();
+    let mut add = |name, value: Option<String>, template| {
+        if let Some(value) = value {
+            res.push(Interpretation {
+                name,
+                value,
+                template,
+            });
+        }
+    };
+    add("u8", fmt_int::<1>(bytes, false, false), Some("u8"));
+    add("i8", fmt_int::<1>(bytes, false, true), Some("i8"));
+    for be in [false, true] {
+        let e = |le, be_| if be { be_ } else { le };
+        add(e("u16 le", "u16 be"), fmt_int::<2>(bytes, be, false), Some(e("u16", "u16 be")));
+        add(e("i16 le", "i16 be"), fmt_int::<2>(bytes, be, true), Some(e("i16", "i16 be")));
+        add(e("u32 le", "u32 be"), fmt_int::<4>(bytes, be, false), Some(e("u32", "u32 be")));
+        add(e("i32 le", "i32 be"), fmt_int::<4>(bytes, be, true), Some(e("i32", "i32 be")));
+        add(e("u64 le", "u64 be"), fmt_int::<8>(bytes, be, false), Some(e("u64", "u64 be")));
+        add(e("i64 le", "i64 be"), fmt_int::<8>(bytes, be, true), Some(e("i64", "i64 be")));
+        let f32_val = int::<4>(bytes, be).map(|b| format!("{}", f32::from_be_bytes(b)));
+        add(e("f32 le", "f32 be"), f32_val, Some(e("f32", "f32 be")));
+        let f64_val = int::<8>(bytes, be).map(|b| format!("{}", f64::from_be_bytes(b)));
+        add(e("f64 le", "f64 be"), f64_val, Some(e("f64", "f64 be")));
+    }
+    let vlq = decode_vlq(bytes).map(|(val, n)| format!("{} ({} bytes)", val, n));
+    add("VLQ", vlq, None);
+    let time = int::<4>(bytes, false).map(|b| fmt_unix_time(u32::from_be_bytes(b) as i64));
+    add("Unix time u32 le", time, Some("u32"));
+    let time = int::<8>(bytes, false).and_then(|b| {
+        let secs = i64::from_be_bytes(b);
+        // far outside of this range it's certainly not a timestamp
+        (secs.abs() < 1 << 40).then(|| fmt_unix_time(secs))
+    });
+    add("Unix time i64 le", time, Some("i64"));
+    add("GUID", fmt_guid(bytes), Some("16 bytes"));
+    res
+}
+
+#[derive(Default)]
+pub struct Inspector {
+    pub is_open: bool,
+    /// Extra bits added to the cursor offset.
+    pub bit: usize,
+}
+
+impl Inspector {
+    /// Show interpretations of the bytes, return the clicked one.
+    pub fn ui<'a>(
+        &mut self,
+        ui: &mut Ui,
+        theme: &Theme,
+        items: &'a [Interpretation],
+    ) -> Option<&'a Interpretation> {
+        let mut clicked = None;
+        ui.horizontal(|ui| {
+            ui.label("Bit:");
+            ui.add(DragValue::new(&mut self.bit).range(0..=7));
+        });
+        if items.is_empty() {
+            ui.colored_label(theme.comment, "Click a byte in the hex view");
+            return None;
+        }
+        Grid::new("inspector").striped(true).show(ui, |ui| {
+            for it in items {
+                ui.colored_label(theme.comment, it.name);
+                let text = RichText::new(it.value.as_str()).monospace().color(theme.code);
+                let resp = ui
+                    .add(egui::Label::new(text).sense(Sense::click()))
+                    .on_hover_text("Click to insert read code");
+                if resp.clicked() {
+                    clicked = Some(it);
+                }
+                ui.end_row();
+            }
+        });
+        clicked
+    }
+}
+
+/// Code reading the interpretation into a new var, with the helper word if needed.
+pub fn snippet(it: &Interpretation, var: &str, seek: Option<usize>, has_vlq: bool) -> String {
+    let mut code = String::new();
+    if it.template.is_none() && !has_vlq {
+        code.push_str(VLQ_WORD);
+    }
+    if let Some(pos) = seek {
+        code.push_str(&format!("{} seek ", pos));
+    }
+    match it.template {
+        Some(t) => code.push_str(&format!("{} read var {}", t, var)),
+        None => code.push_str(&format!("decode-vlq var {}", var)),
+    }
+    code
+}
@@ -0,0 +1,772 @@
+//! Translate Kaitai Struct `.ksy` specs into xeh parsers.
+//!
+//! Every user type becomes a `read-<type>` word returning a map of its fields,
+//! the root type is read into top level variables. Constructs without a xeh
+//! counterpart are listed in `Import::unsupported` and left as comments.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use yaml_rust::{Yaml, YamlLoader};
+
+pub struct Import {
+    /// `meta/id` of the spec.
+    pub name: String,
+    pub source: String,
+    pub unsupported: Vec<String>,
+}
+
+/// Words the generated code calls, fields with these names get renamed.
+const RESERVED: &[&str] = &[
+    "offset", "remain", "input", "read", "seek", "bytes", "magic", "get", "length", "not", "and",
+    "or", "xor", "min", "max", "abs", "drop", "dup", "swap", "over", "rot", "if", "else", "endif",
+    "do", "loop", "begin", "while", "repeat", "until", "local", "var", "nil", "cstr", "I", "J",
+    "K",
+];
+
+/// Keys of seq attributes and instances handled by the translation.
+const KNOWN_KEYS: &[&str] = &[
+    "id",
+    "type",
+    "size",
+    "size-eos",
+    "contents",
+    "repeat",
+    "repeat-expr",
+    "enum",
+    "if",
+    "encoding",
+    "doc",
+    "doc-ref",
+    "-orig-id",
+    "pos",
+    "value",
+];
+
+fn xeh_name(id: &str) -> String {
+    let name = id.replace('_', "-");
+    if RESERVED.contains(&name.as_str()) {
+        format!("{}-field", name)
+    } else {
+        name
+    }
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Last segment of a `a::b` type or enum reference.
+fn local_name(path: &str) -> &str {
+    path.rsplit("::").next().unwrap_or(path)
+}
+
+fn yaml_text(y: &Yaml) -> Option<String> {
+    match y {
+        Yaml::String(s) | Yaml::Real(s) => Some(s.clone()),
+        Yaml::Integer(i) => Some(i.to_string()),
+        Yaml::Boolean(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Tok<'a> {
+    Num(&'a str),
+    Ident(&'a str),
+    Op(&'a str),
+}
+
+fn tokenize(expr: &str) -> Result<Vec<Tok<'_>>, String> {
+    const OPS: &[&str] = &[
+        "<<", ">>", "<=", ">=", "==", "!=", "+", "-", "*", "/", "%", "&", "|", "^", "<", ">", "(",
+        ")", "[", "]", ".", "?", ":", "~",
+    ];
+    let mut toks = Vec::new();
+    let mut rest = expr.trim_start();
+    while let Some(c) = rest.chars().next() {
+        let len = if c.is_ascii_digit() {
+            let n = rest
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(rest.len());
+            toks.push(Tok::Num(&rest[..n]));
+            n
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let n = rest
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(rest.len());
+            toks.push(Tok::Ident(&rest[..n]));
+            n
+        } else if let Some(op) = OPS.iter().find(|op| rest.starts_with(*op)) {
+            toks.push(Tok::Op(op));
+            op.len()
+        } else {
+            return Err(format!("unexpected `{}`", c));
+        };
+        rest = rest[len..].trim_start();
+    }
+    Ok(toks)
+}
+
+fn binary_op(tok: Tok<'_>) -> Option<(u8, &'static str)> {
+    let op = match tok {
+        Tok::Op(op) => op,
+        Tok::Ident(op) => op,
+        Tok::Num(_) => return None,
+    };
+    Some(match op {
+        "or" => (1, "or"),
+        "and" => (2, "and"),
+        "==" => (3, "="),
+        "!=" => (3, "<>"),
+        "<" => (4, "<"),
+        "<=" => (4, "<="),
+        ">" => (4, ">"),
+        ">=" => (4, ">="),
+        "|" => (5, "or"),
+        "^" => (6, "xor"),
+        "&" => (7, "and"),
+        "<<" => (8, "lshift"),
+        ">>" => (8, "rshift"),
+        "+" => (9, "+"),
+        "-" => (9, "-"),
+        "*" => (10, "*"),
+        "/" => (10, "/"),
+        "%" => (10, "reminder"),
+        _ => return None,
+    })
+}
+
+/// Infix Kaitai expression to postfix xeh code.
+struct ExprParser<'a> {
+    toks: Vec<Tok<'a>>,
+    pos: usize,
+}
+
+impl<'a> ExprParser<'a> {
+    fn peek(&self) -> Option<Tok<'a>> {
+        self.toks.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Result<Tok<'a>, String> {
+        let t = self.peek().ok_or("unexpected end of expression")?;
+        self.pos += 1;
+        Ok(t)
+    }
+
+    fn expect(&mut self, op: &str) -> Result<(), String> {
+        match self.next()? {
+            Tok::Op(o) if o == op => Ok(()),
+            t => Err(format!("expected `{}`, found {:?}", op, t)),
+        }
+    }
+
+    fn expr(&mut self, min_prec: u8) -> Result<String, String> {
+        let mut lhs = self.unary()?;
+        while let Some((prec, word)) = self.peek().and_then(binary_op) {
+            if prec < min_prec {
+                break;
+            }
+            self.pos += 1;
+            let rhs = self.expr(prec + 1)?;
+            lhs = format!("{} {} {}", lhs, rhs, word);
+        }
+        if self.peek() == Some(Tok::Op("?")) {
+            return Err("the ternary operator is not supported".to_string());
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<String, String> {
+        match self.peek() {
+            Some(Tok::Op("-")) => {
+                self.pos += 1;
+                Ok(format!("{} negate", self.unary()?))
+            }
+            Some(Tok::Op("~")) => {
+                self.pos += 1;
+                Ok(format!("{} invert", self.unary()?))
+            }
+            Some(Tok::Ident("not")) => {
+                self.pos += 1;
+                Ok(format!("{} not", self.unary()?))
+            }
+            _ => self.postfix(),
+        }
+    }
+
+    fn postfix(&mut self) -> Result<String, String> {
+        let mut code = match self.next()? {
+            Tok::Num(n) => n.to_string(),
+            Tok::Op("(") => {
+                let e = self.expr(0)?;
+                self.expect(")")?;
+                e
+            }
+            Tok::Ident("_io") => {
+                self.expect(".")?;
+                match self.next()? {
+                    Tok::Ident("pos") => "offset 8 /".to_string(),
+                    Tok::Ident("size") => "input bitstr-len 8 /".to_string(),
+                    Tok::Ident("eof") => "remain 0 =".to_string(),
+                    t => return Err(format!("_io.{:?} is not supported", t)),
+                }
+            }
+            Tok::Ident(id @ ("_root" | "_parent" | "_" | "_index" | "true" | "false")) => {
+                return Err(format!("`{}` is not supported", id));
+            }
+            Tok::Ident(id) => xeh_name(id),
+            t => return Err(format!("unexpected {:?}", t)),
+        };
+        loop {
+            match self.peek() {
+                Some(Tok::Op(".")) => {
+                    self.pos += 1;
+                    code = match self.next()? {
+                        Tok::Ident("length") => format!("{} length", code),
+                        Tok::Ident("size") => format!("{} bitstr-len 8 /", code),
+                        Tok::Ident("to_i") => format!("{} str>number", code),
+                        Tok::Ident(key) if !key.starts_with("to_") => {
+                            format!("{} {} get", code, quote(key))
+                        }
+                        t => return Err(format!("method {:?} is not supported", t)),
+                    };
+                }
+                Some(Tok::Op("[")) => {
+                    self.pos += 1;
+                    let index = self.expr(0)?;
+                    self.expect("]")?;
+                    code = format!("{} {} get", code, index);
+                }
+                _ => return Ok(code),
+            }
+        }
+    }
+}
+
+pub fn translate_expr(expr: &str) -> Result<String, String> {
+    let mut p = ExprParser {
+        toks: tokenize(expr)?,
+        pos: 0,
+    };
+    let code = p.expr(0)?;
+    match p.peek() {
+        None => Ok(code),
+        Some(t) => Err(format!("unexpected {:?}", t)),
+    }
+}
+
+struct Gen<'a> {
+    big_endian: bool,
+    types: HashMap<String, &'a Yaml>,
+    enums: HashMap<String, &'a Yaml>,
+    /// Word definitions in the order they must appear.
+    words: Vec<String>,
+    done: HashSet<String>,
+    in_progress: HashSet<String>,
+    unsupported: Vec<String>,
+}
+
+/// Where the fields are stored.
+#[derive(Clone, Copy, PartialEq)]
+enum Scope {
+    Root,
+    Word,
+}
+
+impl Scope {
+    fn declare(&self) -> &'static str {
+        match self {
+            Scope::Root => "var",
+            Scope::Word => "local",
+        }
+    }
+}
+
+impl<'a> Gen<'a> {
+    fn collect_types(&mut self, spec: &'a Yaml) {
+        if let Some(types) = spec["types"].as_hash() {
+            for (name, t) in types {
+                if let Some(name) = name.as_str() {
+                    self.types.entry(name.to_string()).or_insert(t);
+                    self.collect_types(t);
+                }
+            }
+        }
+        if let Some(enums) = spec["enums"].as_hash() {
+            for (name, e) in enums {
+                if let Some(name) = name.as_str() {
+                    self.enums.entry(name.to_string()).or_insert(e);
+                }
+            }
+        }
+    }
+
+    fn report(&mut self, out: &mut String, at: &str, what: &str) {
+        let msg = format!("{}: {}", at, what);
+        let _ = writeln!(out, "\\ TODO unsupported {}", msg);
+        self.unsupported.push(msg);
+    }
+
+    fn expr(&mut self, out: &mut String, at: &str, y: &Yaml) -> Option<String> {
+        let Some(text) = yaml_text(y) else {
+            self.report(out, at, "expression is not a scalar");
+            return None;
+        };
+        match translate_expr(&text) {
+            Ok(code) => Some(code),
+            Err(e) => {
+                self.report(out, at, &format!("`{}`, {}", text, e));
+                None
+            }
+        }
+    }
+
+    fn primitive(&self, t: &str) -> Option<String> {
+        let (base, endian) = match t.strip_suffix("le").or_else(|| t.strip_suffix("be")) {
+            Some(base) if base.len() > 1 => (base, Some(t.ends_with("be"))),
+            _ => (t, None),
+        };
+        let (kind, n) = base.split_at(1);
+        let n: usize = n.parse().ok()?;
+        let template = match (kind, n) {
+            ("u", 1 | 2 | 4 | 8) => format!("u{}", n * 8),
+            ("s", 1 | 2 | 4 | 8) => format!("i{}", n * 8),
+            ("f", 4 | 8) => format!("f{}", n * 8),
+            ("b", 1..=64) => return Some(format!("u{} read", n)),
+            _ => return None,
+        };
+        let big = endian.unwrap_or(self.big_endian);
+        match (n, big, endian) {
+            (1, _, _) => Some(format!("{} read", template)),
+            (_, true, _) => Some(format!("{} be read", template)),
+            (_, false, Some(_)) => Some(format!("{} le read", template)),
+            _ => Some(format!("{} read", template)),
+        }
+    }
+
+    fn contents(&mut self, out: &mut String, at: &str, y: &Yaml) -> Option<String> {
+        let item = |y: &Yaml| match y {
+            Yaml::Integer(i) => Some(format!("{:#04x}", i)),
+            Yaml::String(s) => Some(quote(s)),
+            _ => None,
+        };
+        let items: Option<Vec<String>> = match y {
+            Yaml::Array(a) => a.iter().map(item).collect(),
+            y => item(y).map(|i| vec![i]),
+        };
+        match items {
+            Some(items) => Some(format!("[ {} ] >bitstr magic", items.join(" "))),
+            None => {
+                self.report(out, at, "contents must be bytes or strings");
+                None
+            }
+        }
+    }
+
+    /// Byte count of `size` or `size-eos`.
+    fn size(&mut self, out: &mut String, at: &str, attr: &Yaml) -> Option<Option<String>> {
+        if attr["size-eos"].as_bool() == Some(true) {
+            return Some(Some("remain 8 /".to_string()));
+        }
+        if attr["size"].is_badvalue() {
+            return Some(None);
+        }
+        self.expr(out, at, &attr["size"]).map(Some)
+    }
+
+    fn enum_word(&mut self, out: &mut String, at: &str, name: &str) -> Option<String> {
+        let name = local_name(name);
+        let word = format!("enum-{}", xeh_name(name));
+        if self.done.contains(&word) {
+            return Some(word);
+        }
+        let Some(values) = self.enums.get(name).and_then(|e| e.as_hash()) else {
+            self.report(out, at, &format!("enum `{}` not found", name));
+            return None;
+        };
+        let mut def = format!(": {}\n    local v\n", word);
+        let mut n = 0;
+        for (k, v) in values {
+            let label = match v {
+                Yaml::Hash(_) => v["id"].as_str(),
+                v => v.as_str(),
+            };
+            if let (Some(k), Some(label)) = (yaml_text(k), label) {
+                let _ = writeln!(def, "    v {} = if {} else", k, quote(label));
+                n += 1;
+            }
+        }
+        let _ = writeln!(def, "    v{}\n;", " endif".repeat(n));
+        self.words.push(def);
+        self.done.insert(word.clone());
+        Some(word)
+    }
+
+    fn type_word(&mut self, out: &mut String, at: &str, name: &str) -> Option<String> {
+        let name = local_name(name);
+        let word = format!("read-{}", xeh_name(name));
+        if self.done.contains(&word) {
+            return Some(word);
+        }
+        if self.in_progress.contains(&word) {
+            self.report(out, at, &format!("recursive type `{}`", name));
+            return None;
+        }
+        let Some(spec) = self.types.get(name).copied() else {
+            self.report(out, at, &format!("type `{}` not found", name));
+            return None;
+        };
+        if !spec["params"].is_badvalue() {
+            self.report(out, at, &format!("parameters of type `{}`", name));
+        }
+        self.in_progress.insert(word.clone());
+        let mut body = String::new();
+        let fields = self.type_body(&mut body, name, spec, Scope::Word);
+        let mut def = format!(": {}\n", word);
+        for line in body.lines() {
+            let _ = writeln!(def, "    {}", line);
+        }
+        let map: Vec<String> = fields
+            .iter()
+            .map(|f| format!("{} {}", quote(f), xeh_name(f)))
+            .collect();
+        let _ = writeln!(def, "    {{ {} }}\n;", map.join(" "));
+        self.in_progress.remove(&word);
+        self.words.push(def);
+        self.done.insert(word.clone());
+        Some(word)
+    }
+
+    /// Code pushing a single value of the attribute.
+    fn value(&mut self, out: &mut String, at: &str, attr: &Yaml) -> Option<String> {
+        if !attr["contents"].is_badvalue() {
+            return self.contents(out, at, &attr["contents"]);
+        }
+        if !attr["value"].is_badvalue() {
+            return self.expr(out, at, &attr["value"]);
+        }
+        let size = self.size(out, at, attr)?;
+        let read = match &attr["type"] {
+            Yaml::BadValue => match size {
+                Some(size) => format!("{} bytes read", size),
+                None => {
+                    self.report(out, at, "no type and no size");
+                    return None;
+                }
+            },
+            Yaml::Hash(_) => self.switch(out, at, attr, size)?,
+            t => {
+                let t = t.as_str().unwrap_or_default();
+                self.typed(out, at, attr, t, size)?
+            }
+        };
+        match attr["enum"].as_str() {
+            Some(e) => {
+                let word = self.enum_word(out, at, e)?;
+                Some(format!("{} dup {} \"enum\" swap insert-tag", read, word))
+            }
+            None => Some(read),
+        }
+    }
+
+    fn typed(
+        &mut self,
+        out: &mut String,
+        at: &str,
+        attr: &Yaml,
+        t: &str,
+        size: Option<String>,
+    ) -> Option<String> {
+        if let Some(p) = self.primitive(t) {
+            return Some(p);
+        }
+        match t {
+            "strz" => {
+                if attr["terminator"].as_i64().is_some_and(|t| t != 0) {
+                    self.report(out, at, "terminator other than 0");
+                }
+                Some(match size {
+                    // the string ends at the NUL, the field at its size
+                    Some(size) => format!("{} bytes read open-input cstr read close-input", size),
+                    None => "cstr read".to_string(),
+                })
+            }
+            "str" => {
+                let encoding = attr["encoding"]
+                    .as_str()
+                    .unwrap_or("UTF-8")
+                    .to_ascii_uppercase();
+                if !["UTF-8", "UTF8", "ASCII"].contains(&encoding.as_str()) {
+                    self.report(out, at, &format!("encoding {}, read as UTF-8", encoding));
+                }
+                let nul = attr["terminator"].as_i64() == Some(0);
+                match size {
+                    Some(size) if nul => Some(format!(
+                        "{} bytes read open-input cstr read close-input",
+                        size
+                    )),
+                    Some(size) => Some(format!("{} bytes read bitstr>utf8", size)),
+                    None if nul => Some("cstr read".to_string()),
+                    None => {
+                        self.report(out, at, "str without size");
+                        None
+                    }
+                }
+            }
+            user => {
+                let word = self.type_word(out, at, user)?;
+                match size {
+                    // parse the type from its own sub-stream
+                    Some(size) => Some(format!(
+                        "{} bytes read open-input {} close-input",
+                        size, word
+                    )),
+                    None => Some(word),
+                }
+            }
+        }
+    }
+
+    fn switch(
+        &mut self,
+        out: &mut String,
+        at: &str,
+        attr: &Yaml,
+        size: Option<String>,
+    ) -> Option<String> {
+        let t = &attr["type"];
+        let on = self.expr(out, at, &t["switch-on"])?;
+        let Some(cases) = t["cases"].as_hash() else {
+            self.report(out, at, "switch without cases");
+            return None;
+        };
+        let mut code = String::new();
+        let mut n = 0;
+        let mut default = None;
+        for (k, v) in cases {
+            let (Some(k), Some(v)) = (yaml_text(k), v.as_str()) else {
+                continue;
+            };
+            let read = self.typed(out, at, attr, v, size.clone())?;
+            if k == "_" {
+                default = Some(read);
+                continue;
+            }
+            let k = match translate_expr(&k) {
+                Ok(k) => k,
+                Err(e) => {
+                    self.report(out, at, &format!("case `{}`, {}", k, e));
+                    continue;
+                }
+            };
+            let _ = write!(code, "{} {} = if {} else ", on, k, read);
+            n += 1;
+        }
+        let default = match (default, size) {
+            (Some(read), _) => read,
+            (None, Some(size)) => format!("{} bytes read", size),
+            (None, None) => "nil".to_string(),
+        };
+        let _ = write!(code, "{}{}", default, " endif".repeat(n));
+        Some(code)
+    }
+
+    /// Translate one seq item or instance, return the field name.
+    fn attribute(
+        &mut self,
+        out: &mut String,
+        ctx: &str,
+        id: &str,
+        attr: &Yaml,
+        scope: Scope,
+    ) -> Option<String> {
+        let at = format!("{}.{}", ctx, id);
+        if let Some(keys) = attr.as_hash() {
+            for k in keys.keys().filter_map(|k| k.as_str()) {
+                if !KNOWN_KEYS.contains(&k) {
+                    self.report(out, &at, &format!("`{}` is ignored", k));
+                }
+            }
+        }
+        if let Some(doc) = attr["doc"].as_str() {
+            for line in doc.lines() {
+                let _ = writeln!(out, "\\ {}", line);
+            }
+        }
+        let name = xeh_name(id);
+        let mut code = self.value(out, &at, attr)?;
+        match attr["repeat"].as_str() {
+            None => (),
+            Some("eos") => code = format!("[ begin remain 0 > while {} repeat ]", code),
+            Some("expr") => {
+                let n = self.expr(out, &at, &attr["repeat-expr"])?;
+                code = format!("[ {} 0 do {} loop ]", n, code);
+            }
+            Some(r) => {
+                self.report(out, &at, &format!("repeat: {}", r));
+                return None;
+            }
+        }
+        if !attr["if"].is_badvalue() {
+            let cond = self.expr(out, &at, &attr["if"])?;
+            code = format!("{} if {} else nil endif", cond, code);
+        }
+        if !attr["pos"].is_badvalue() {
+            let pos = self.expr(out, &at, &attr["pos"])?;
+            let ret = format!("{}-return", name);
+            let _ = writeln!(out, "offset {} {}", scope.declare(), ret);
+            let _ = writeln!(out, "{} >b seek", pos);
+            let _ = writeln!(out, "{} {} {}", code, scope.declare(), name);
+            let _ = writeln!(out, "{} seek", ret);
+        } else {
+            let _ = writeln!(out, "{} {} {}", code, scope.declare(), name);
+        }
+        Some(id.to_string())
+    }
+
+    /// Fields of the seq and the instances, return their ids.
+    fn type_body(&mut self, out: &mut String, ctx: &str, spec: &Yaml, scope: Scope) -> Vec<String> {
+        let mut fields = Vec::new();
+        for (i, attr) in spec["seq"]
+            .as_vec()
+            .map(|v| v.as_slice())
+            .unwrap_or_default()
+            .iter()
+            .enumerate()
+        {
+            let id = attr["id"]
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("unnamed{}", i));
+            fields.extend(self.attribute(out, ctx, &id, attr, scope));
+        }
+        if let Some(instances) = spec["instances"].as_hash() {
+            for (id, attr) in instances {
+                let Some(id) = id.as_str() else {
+                    continue;
+                };
+                if !attr["io"].is_badvalue() {
+                    self.report(out, &format!("{}.{}", ctx, id), "io of instances");
+                    continue;
+                }
+                fields.extend(self.attribute(out, ctx, id, attr, scope));
+            }
+        }
+        fields
+    }
+}
+
+pub fn import(text: &str) -> Result<Import, String> {
+    let docs = YamlLoader::load_from_str(text).map_err(|e| e.to_string())?;
+    let spec = docs.first().ok_or("empty document")?;
+    let meta = &spec["meta"];
+    let name = meta["id"].as_str().unwrap_or("ksy").to_string();
+    let mut gen = Gen {
+        big_endian: false,
+        types: HashMap::new(),
+        enums: HashMap::new(),
+        words: Vec::new(),
+        done: HashSet::new(),
+        in_progress: HashSet::new(),
+        unsupported: Vec::new(),
+    };
+    let mut top = String::new();
+    match &meta["endian"] {
+        Yaml::BadValue => (),
+        Yaml::String(e) if e == "le" => (),
+        Yaml::String(e) if e == "be" => gen.big_endian = true,
+        _ => gen.report(&mut top, "meta", "endian switch, little endian is used"),
+    }
+    if meta["bit-endian"].as_str() == Some("le") {
+        gen.report(&mut top, "meta", "little endian bit fields");
+    }
+    if !meta["imports"].is_badvalue() {
+        gen.report(&mut top, "meta", "imports");
+    }
+    gen.collect_types(spec);
+    gen.type_body(&mut top, &name, spec, Scope::Root);
+
+    let mut source = format!("\\ generated from {}.ksy\n\n", name);
+    for w in &gen.words {
+        source.push_str(w);
+        source.push('\n');
+    }
+    source.push_str(&top);
+    Ok(Import {
+        name,
+        source,
+        unsupported: gen.unsupported,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn precedence() {
+        assert_eq!(translate_expr("1 + 2 * 3").unwrap(), "1 2 3 * +");
+        assert_eq!(translate_expr("(1 + 2) * 3").unwrap(), "1 2 + 3 *");
+        assert_eq!(translate_expr("a - b - c").unwrap(), "a b - c -");
+    }
+
+    #[test]
+    fn io_and_fields() {
+        assert_eq!(translate_expr("_io.pos").unwrap(), "offset 8 /");
+        assert_eq!(translate_expr("_io.eof").unwrap(), "remain 0 =");
+        assert_eq!(
+            translate_expr("hdr.num_files").unwrap(),
+            "hdr \"num_files\" get"
+        );
+        assert!(translate_expr("_root.len").is_err());
+        assert!(translate_expr("_io.foo").is_err());
+        assert!(translate_expr("1 +").is_err());
+    }
+
+    const SPEC: &str = "
+meta:
+  id: demo
+  endian: be
+seq:
+  - id: magic
+    contents: [0x4d, 0x5a]
+  - id: kind
+    type: u1
+    enum: kinds
+  - id: name
+    type: strz
+    size: 16
+  - id: body
+    type:
+      switch-on: kind
+      cases:
+        1: header
+        _: u2
+  - id: extra
+    type: u4
+    process: xor(0xff)
+types:
+  header:
+    seq:
+      - id: len
+        type: u2
+enums:
+  kinds:
+    1: header
+    2: data
+";
+
+    #[test]
+    fn import_spec() {
+        let import = import(SPEC).unwrap();
+        assert_eq!(import.name, "demo");
+        let src = &import.source;
+        assert!(src.contains("[ 0x4d 0x5a ] >bitstr magic var magic-field"));
+        assert!(src.contains(": enum-kinds"));
+        assert!(src.contains("u8 read dup enum-kinds \"enum\" swap insert-tag var kind"));
+        assert!(src.contains("16 bytes read open-input cstr read close-input var name"));
+        assert!(src.contains(": read-header"));
+        assert!(src.contains("kind 1 = if read-header else u16 be read endif var body"));
+        assert_eq!(import.unsupported, vec!["demo.extra: `process` is ignored"]);
+    }
+}
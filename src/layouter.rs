@@ -1,25 +1,163 @@
 use crate::style::*;
 use eframe::egui;
 use eframe::egui::text::TextFormat;
+use std::collections::HashSet;
+use std::ops::Range;
 use xeh::prelude::*;
-use syntect::easy::HighlightLines;
-use syntect::highlighting::{ThemeSet, Style};
-use syntect::parsing::SyntaxSet;
-use syntect::util::LinesWithEndings;
-use std::sync::OnceLock;
 
-static SYNTAX_SET: OnceLock<SyntaxSet> = OnceLock::new();
-static THEME_SET: OnceLock<ThemeSet> = OnceLock::new();
+/// Names known to the VM, used to tell apart words and variables.
+#[derive(Default)]
+pub struct Lexicon {
+    pub builtins: HashSet<String>,
+    pub words: HashSet<String>,
+    pub vars: HashSet<String>,
+}
+
+impl Lexicon {
+    pub fn new(xs: &Xstate) -> Self {
+        let builtins = xs.word_list().iter().map(|w| w.to_string()).collect();
+        Self {
+            builtins,
+            words: HashSet::new(),
+            vars: HashSet::new(),
+        }
+    }
+
+    /// Collect words and variables defined after boot.
+    pub fn update(&mut self, xs: &Xstate, vars_boot_len: usize) {
+        self.words = xs
+            .word_list()
+            .iter()
+            .map(|w| w.to_string())
+            .filter(|w| !self.builtins.contains(w))
+            .collect();
+        let lst = xs.var_list();
+        let n = lst.len().saturating_sub(vars_boot_len);
+        self.vars = lst.iter().rev().take(n).map(|(name, _)| name.to_string()).collect();
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TokenKind {
+    Space,
+    Comment,
+    Str,
+    Bitstr,
+    Number,
+    Delimiter,
+    Builtin,
+    Word,
+    Var,
+    Unknown,
+}
+
+fn is_number(s: &str) -> bool {
+    let s = s.strip_prefix('-').unwrap_or(s);
+    let digits = |s: &str, radix| !s.is_empty() && s.chars().all(|c| c == '_' || c.is_digit(radix));
+    if let Some(x) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        digits(x, 16)
+    } else if let Some(x) = s.strip_prefix("0b").or_else(|| s.strip_prefix("0B")) {
+        digits(x, 2)
+    } else if let Some(x) = s.strip_prefix("0o").or_else(|| s.strip_prefix("0O")) {
+        digits(x, 8)
+    } else {
+        s.starts_with(|c: char| c.is_ascii_digit()) && s.parse::<f64>().is_ok()
+    }
+}
+
+/// Skip until the closing `end` marker, return the offset after it.
+fn skip_until(text: &str, from: usize, end: &str) -> usize {
+    text[from..].find(end).map(|i| from + i + end.len()).unwrap_or(text.len())
+}
 
-fn get_syntax_set() -> &'static SyntaxSet {
-    SYNTAX_SET.get_or_init(|| SyntaxSet::load_defaults_newlines())
+/// Split xeh source into highlighted tokens covering the whole text.
+pub fn tokenize(text: &str, lex: &Lexicon) -> Vec<(Range<usize>, TokenKind)> {
+    // words and variables introduced in the text itself
+    let mut defs = HashSet::new();
+    let mut vars = HashSet::new();
+    let mut prev = "";
+    for w in text.split_whitespace() {
+        match prev {
+            ":" => {
+                defs.insert(w);
+            }
+            "var" | "local" | "let" | "const" | "->" => {
+                vars.insert(w);
+            }
+            _ => (),
+        }
+        prev = w;
+    }
+    let mut res = Vec::new();
+    let bytes = text.as_bytes();
+    let mut pos = 0;
+    while pos < text.len() {
+        let start = pos;
+        if bytes[pos].is_ascii_whitespace() {
+            while pos < text.len() && bytes[pos].is_ascii_whitespace() {
+                pos += 1;
+            }
+            res.push((start..pos, TokenKind::Space));
+            continue;
+        }
+        let kind = match bytes[pos] {
+            b'"' => {
+                pos += 1;
+                while pos < text.len() && bytes[pos] != b'"' {
+                    pos += if bytes[pos] == b'\\' { 2 } else { 1 };
+                }
+                pos = (pos + 1).min(text.len());
+                TokenKind::Str
+            }
+            b'|' => {
+                pos = skip_until(text, pos + 1, "|");
+                TokenKind::Bitstr
+            }
+            _ => {
+                while pos < text.len() && !bytes[pos].is_ascii_whitespace() {
+                    pos += 1;
+                }
+                let tok = &text[start..pos];
+                match tok {
+                    "\\" => {
+                        pos = text[pos..].find('\n').map(|i| pos + i).unwrap_or(text.len());
+                        TokenKind::Comment
+                    }
+                    "\\(" => {
+                        pos = skip_until(text, pos, "\\)");
+                        TokenKind::Comment
+                    }
+                    ":" | ";" | "[" | "]" | "{" | "}" | "#(" | "#)" | "^{" | "^}" => {
+                        TokenKind::Delimiter
+                    }
+                    _ if is_number(tok) => TokenKind::Number,
+                    _ if lex.vars.contains(tok) || vars.contains(tok) => TokenKind::Var,
+                    _ if lex.words.contains(tok) || defs.contains(tok) => TokenKind::Word,
+                    _ if lex.builtins.contains(tok) => TokenKind::Builtin,
+                    _ => TokenKind::Unknown,
+                }
+            }
+        };
+        res.push((start..pos, kind));
+    }
+    res
 }
 
-fn get_theme_set() -> &'static ThemeSet {
-    THEME_SET.get_or_init(|| ThemeSet::load_defaults())
+/// Byte offset of the word name in its `: name ... ;` definition.
+pub fn find_definition(text: &str, name: &str) -> Option<usize> {
+    let lex = Lexicon::default();
+    let mut colon = false;
+    for (range, kind) in tokenize(text, &lex) {
+        match kind {
+            TokenKind::Space => continue,
+            _ if colon && &text[range.clone()] == name => return Some(range.start),
+            _ => colon = kind == TokenKind::Delimiter && &text[range] == ":",
+        }
+    }
+    None
 }
 
-pub fn word_under_cursor(s: &String, char_index: Option<usize>) -> Option<String> {
+pub fn word_range_under_cursor(s: &str, char_index: Option<usize>) -> Option<Range<usize>> {
     let char_index = char_index?;
     let mut it = s.char_indices();
     let mut start = 0;
@@ -46,16 +184,21 @@ pub fn word_under_cursor(s: &String, char_index: Option<usize>) -> Option<String
         cur_index += 1;
     }
     if end - start < 1000 {
-        Some(s[start..end].to_string())
+        Some(start..end)
     } else {
         None
     }
 }
 
+pub fn word_under_cursor(s: &str, char_index: Option<usize>) -> Option<String> {
+    word_range_under_cursor(s, char_index).map(|r| s[r].to_string())
+}
+
 pub fn code_layouter(
     text: &str,
     err: Option<&Xsubstr>,
     dbg: Option<&Xsubstr>,
+    lex: &Lexicon,
     font_id: &egui::FontId,
     wrap_width: f32,
     theme: &Theme,
@@ -64,13 +207,6 @@ pub fn code_layouter(
     j.text = text.to_string(); // This effectively sets the text for all sections to reference
     j.wrap.max_width = wrap_width;
 
-    let ps = get_syntax_set();
-    let ts = get_theme_set();
-    // Use Rust syntax highlighting as a fallback/default for xeh if no specific one exists
-    let syntax = ps.find_syntax_by_extension("rs").unwrap_or_else(|| ps.find_syntax_plain_text());
-    let syn_theme = &ts.themes["base16-ocean.dark"];
-    let mut h = HighlightLines::new(syntax, syn_theme);
-
     let mut err_range = None;
     if let Some(s) = err {
         let start = s.range().start.min(text.len());
@@ -85,52 +221,155 @@ pub fn code_layouter(
         dbg_range = Some(start..end);
     }
 
-    // Iterate over lines to apply syntax highlighting
-    // Note: egui LayoutJob expects byte ranges into the original text.
-    // syntect works line by line. We need to track global byte offset.
-    let mut global_offset = 0;
-
-    for line in LinesWithEndings::from(text) {
-        let ranges: Vec<(Style, &str)> = h.highlight_line(line, ps).unwrap_or_default();
-        for (style, range_text) in ranges {
-            let start = global_offset;
-            let end = global_offset + range_text.len();
+    for (range, kind) in tokenize(text, lex) {
+        let (start, end) = (range.start, range.end);
 
-            // Determine background/underline based on err/dbg ranges
-            let mut background = theme.code_background;
-            let mut underline = egui::Stroke::NONE;
+        // Determine background/underline based on err/dbg ranges
+        let mut background = theme.code_background;
+        let mut underline = egui::Stroke::NONE;
 
-            if let Some(r) = &err_range {
-                if r.start < end && r.end > start {
-                    // Overlaps with error
-                    underline = egui::Stroke::new(1.0, theme.error);
-                }
+        if let Some(r) = &err_range {
+            if r.start < end && r.end > start {
+                // Overlaps with error
+                underline = egui::Stroke::new(1.0, theme.error);
             }
-            if let Some(r) = &dbg_range {
-                if r.start < end && r.end > start {
-                    // Overlaps with debug
-                    background = theme.debug_marker;
-                    underline = egui::Stroke::new(1.0, theme.debug_marker);
-                }
+        }
+        if let Some(r) = &dbg_range {
+            if r.start < end && r.end > start {
+                // Overlaps with debug
+                background = theme.debug_marker;
+                underline = egui::Stroke::new(1.0, theme.debug_marker);
             }
-
-            let color = egui::Color32::from_rgb(style.foreground.r, style.foreground.g, style.foreground.b);
-
-            j.sections.push(egui::text::LayoutSection {
-                leading_space: 0.0,
-                byte_range: start..end,
-                format: TextFormat {
-                    font_id: font_id.clone(),
-                    color,
-                    background,
-                    underline,
-                    ..Default::default()
-                },
-            });
-
-            global_offset = end;
         }
+
+        let color = match kind {
+            TokenKind::Space | TokenKind::Unknown => theme.code,
+            TokenKind::Comment => theme.comment,
+            TokenKind::Str | TokenKind::Bitstr => theme.syntax_string,
+            TokenKind::Number => theme.syntax_number,
+            TokenKind::Delimiter => theme.selection,
+            TokenKind::Builtin => theme.syntax_builtin,
+            TokenKind::Word => theme.syntax_word,
+            TokenKind::Var => theme.syntax_var,
+        };
+
+        j.sections.push(egui::text::LayoutSection {
+            leading_space: 0.0,
+            byte_range: start..end,
+            format: TextFormat {
+                font_id: font_id.clone(),
+                color,
+                background,
+                underline,
+                ..Default::default()
+            },
+        });
     }
 
     j
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens<'a>(text: &'a str, lex: &Lexicon) -> Vec<(&'a str, TokenKind)> {
+        tokenize(text, lex)
+            .into_iter()
+            .filter(|(_, kind)| *kind != TokenKind::Space)
+            .map(|(range, kind)| (&text[range], kind))
+            .collect()
+    }
+
+    #[test]
+    fn covers_the_whole_text() {
+        let text = "  \"a\\\"b\" |ff| 12 \\ note\n: w ;\n";
+        let lex = Lexicon::default();
+        let mut pos = 0;
+        for (range, _) in tokenize(text, &lex) {
+            assert_eq!(range.start, pos);
+            pos = range.end;
+        }
+        assert_eq!(pos, text.len());
+    }
+
+    #[test]
+    fn strings_with_escapes() {
+        let lex = Lexicon::default();
+        assert_eq!(
+            tokens(r#""a \"quoted\" \\" x"#, &lex),
+            vec![
+                (r#""a \"quoted\" \\""#, TokenKind::Str),
+                ("x", TokenKind::Unknown)
+            ]
+        );
+        // an unterminated string runs to the end
+        assert_eq!(tokens("\"abc\\", &lex), vec![("\"abc\\", TokenKind::Str)]);
+    }
+
+    #[test]
+    fn comments() {
+        let lex = Lexicon::default();
+        assert_eq!(
+            tokens("1 \\ one two\n2 \\( inline \\) 3", &lex),
+            vec![
+                ("1", TokenKind::Number),
+                ("\\ one two", TokenKind::Comment),
+                ("2", TokenKind::Number),
+                ("\\( inline \\)", TokenKind::Comment),
+                ("3", TokenKind::Number),
+            ]
+        );
+        // a backslash inside a word doesn't start a comment
+        assert_eq!(tokens("a\\b", &lex), vec![("a\\b", TokenKind::Unknown)]);
+    }
+
+    #[test]
+    fn numbers() {
+        let lex = Lexicon::default();
+        for n in ["0", "-12", "1.5", "0xff_00", "0b1010", "0o17"] {
+            assert_eq!(tokens(n, &lex), vec![(n, TokenKind::Number)], "{}", n);
+        }
+        for w in ["0xg", "0b2", "1x", "-", "e5"] {
+            assert_eq!(tokens(w, &lex), vec![(w, TokenKind::Unknown)], "{}", w);
+        }
+    }
+
+    #[test]
+    fn bitstr_literals() {
+        let lex = Lexicon::default();
+        assert_eq!(
+            tokens("|de ad| |0101 .| 1", &lex),
+            vec![
+                ("|de ad|", TokenKind::Bitstr),
+                ("|0101 .|", TokenKind::Bitstr),
+                ("1", TokenKind::Number),
+            ]
+        );
+    }
+
+    #[test]
+    fn words_end_at_whitespace() {
+        let mut lex = Lexicon::default();
+        lex.builtins.insert("read".to_string());
+        lex.builtins.insert("var".to_string());
+        assert_eq!(
+            tokens(": hdr u32 read ; hdr var h h read-more [ h ]", &lex),
+            vec![
+                (":", TokenKind::Delimiter),
+                ("hdr", TokenKind::Word),
+                ("u32", TokenKind::Unknown),
+                ("read", TokenKind::Builtin),
+                (";", TokenKind::Delimiter),
+                ("hdr", TokenKind::Word),
+                ("var", TokenKind::Builtin),
+                ("h", TokenKind::Var),
+                ("h", TokenKind::Var),
+                ("read-more", TokenKind::Unknown),
+                ("[", TokenKind::Delimiter),
+                ("h", TokenKind::Var),
+                ("]", TokenKind::Delimiter),
+            ]
+        );
+    }
+}
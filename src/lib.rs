@@ -4,12 +4,32 @@
 
 mod agent;
 mod collab;
+mod compare;
+mod completion;
+mod decompress;
+mod disasm;
 mod app;
+mod binary;
+mod bitview;
+mod bookmarks;
 mod canvas;
+mod codegen;
+mod export;
+mod formats;
+mod hash;
+mod hexview;
 mod hotkeys;
+mod inputs;
+mod inspector;
+mod kaitai;
 mod layouter;
 mod style;
+mod output;
+mod overview;
 mod palette;
+mod reads;
+mod search;
+mod tree;
 mod workspace;
 mod file_explorer;
 pub use app::TemplateApp;
@@ -24,7 +24,7 @@ fn main() {
         Box::new(|cc| {
             let mut app = xeh_playground::TemplateApp::new(cc);
             if let Some(path) = std::env::args().skip(1).next() {
-                xeh::file::fs_overlay::load_binary(&mut app.xs, path.as_str()).unwrap();
+                app.open_path(std::path::Path::new(&path));
             }
             Ok(Box::new(app))
         }),
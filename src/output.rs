@@ -0,0 +1,72 @@
+use crate::binary;
+use std::cell::RefCell;
+use xeh::prelude::*;
+
+/// File a script asked to save, the app opens the dialog after the run.
+pub struct SaveRequest {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+thread_local! {
+    static PENDING: RefCell<Vec<SaveRequest>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Save requests queued since the last call.
+pub fn take_pending() -> Vec<SaveRequest> {
+    PENDING.with(|r| r.take())
+}
+
+/// Bytes of the bitstr, the incomplete last byte is padded with zero bits.
+pub fn padded_bytes(bs: &Xbitstr) -> Vec<u8> {
+    bs.iter8()
+        .map(|(b, n)| {
+            if n < 8 {
+                ((b as u32) << (8 - n)) as u8
+            } else {
+                b
+            }
+        })
+        .collect()
+}
+
+pub fn is_byte_aligned(bs: &Xbitstr) -> bool {
+    (bs.end() - bs.start()).is_multiple_of(8)
+}
+
+fn save_word(xs: &mut Xstate, padded: bool) -> Xresult {
+    let name = xs.pop_data()?;
+    let name = name.str()?.to_string();
+    let bs = xs.pop_data()?;
+    let bs = bs.bitstr()?;
+    if !padded && !is_byte_aligned(bs) {
+        let msg = format!(
+            "{} bits is not a whole number of bytes, use output/save-padded",
+            bs.end() - bs.start()
+        );
+        return Err(Xerr::ErrorMsg(Xstr::from(msg)));
+    }
+    let data = if padded {
+        padded_bytes(bs)
+    } else {
+        binary::to_bytes(bs)
+    };
+    PENDING.with(|r| r.borrow_mut().push(SaveRequest { name, data }));
+    Ok(())
+}
+
+fn output_save(xs: &mut Xstate) -> Xresult {
+    save_word(xs, false)
+}
+
+fn output_save_padded(xs: &mut Xstate) -> Xresult {
+    save_word(xs, true)
+}
+
+/// `bitstr "name" output/save --`, the padded variant accepts any bit length.
+pub fn install(xs: &mut Xstate) -> Xresult {
+    PENDING.with(|r| r.borrow_mut().clear());
+    xs.defword("output/save", output_save)?;
+    xs.defword("output/save-padded", output_save_padded)?;
+    Ok(())
+}
@@ -0,0 +1,238 @@
+use crate::style::Theme;
+use egui::{vec2, Color32, Rect, ScrollArea, Sense, Stroke, Ui};
+use std::ops::Range;
+use xeh::prelude::*;
+
+/// Bytes analysed per frame, keeps big inputs from freezing the UI.
+const BYTES_PER_FRAME: usize = 256 * 1024;
+const MAX_BLOCKS: usize = 2048;
+const BLOCK_HEIGHT: f32 = 3.0;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ByteClass {
+    Zeros,
+    Text,
+    Binary,
+    Random,
+}
+
+impl ByteClass {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ByteClass::Zeros => "padding",
+            ByteClass::Text => "text",
+            ByteClass::Binary => "binary",
+            ByteClass::Random => "compressed or encrypted",
+        }
+    }
+
+    fn color(&self) -> Color32 {
+        match self {
+            ByteClass::Zeros => Color32::from_gray(60),
+            ByteClass::Text => Color32::from_rgb(0x4c, 0xaf, 0x50),
+            ByteClass::Binary => Color32::from_rgb(0x21, 0x96, 0xf3),
+            ByteClass::Random => Color32::from_rgb(0xe5, 0x39, 0x35),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Block {
+    /// Shannon entropy in bits per byte, 0 to 8.
+    pub entropy: f32,
+    pub class: ByteClass,
+}
+
+/// Streamed so big blocks aren't copied.
+pub fn analyze(data: impl Iterator<Item = u8>) -> Block {
+    let mut hist = [0usize; 256];
+    let mut len = 0;
+    for b in data {
+        hist[b as usize] += 1;
+        len += 1;
+    }
+    let n = len.max(1) as f32;
+    let entropy = hist
+        .iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| {
+            let p = c as f32 / n;
+            -p * p.log2()
+        })
+        .sum::<f32>();
+    let text: usize = [b'\t', b'\n', b'\r']
+        .iter()
+        .map(|&b| hist[b as usize])
+        .chain(hist[0x20..0x7f].iter().copied())
+        .sum();
+    let class = if hist[0] + hist[0xff] == len {
+        ByteClass::Zeros
+    } else if text as f32 >= 0.9 * n {
+        ByteClass::Text
+    } else if entropy > 7.2 {
+        ByteClass::Random
+    } else {
+        ByteClass::Binary
+    };
+    Block { entropy, class }
+}
+
+#[derive(Default)]
+pub struct Overview {
+    pub is_open: bool,
+    input: Option<Xbitstr>,
+    /// Bytes per block.
+    block_size: usize,
+    blocks: Vec<Block>,
+    /// Bytes analysed so far.
+    done: usize,
+    total: usize,
+}
+
+impl Overview {
+    /// Start over if the input has changed.
+    pub fn set_input(&mut self, bs: &Xbitstr) {
+        let same = self
+            .input
+            .as_ref()
+            .is_some_and(|old| old.start() == bs.start() && old.end() == bs.end());
+        if !same {
+            self.reset(bs);
+        }
+    }
+
+    /// Analyse the input again, e.g. after the bytes were patched.
+    pub fn invalidate(&mut self) {
+        self.input = None;
+    }
+
+    fn reset(&mut self, bs: &Xbitstr) {
+        self.total = (bs.end() - bs.start()).div_ceil(8);
+        self.block_size = self.total.div_ceil(MAX_BLOCKS).max(256).next_power_of_two();
+        self.blocks.clear();
+        self.done = 0;
+        self.input = Some(bs.clone());
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.done >= self.total
+    }
+
+    /// Analyse the next chunk of the input.
+    pub fn step(&mut self) {
+        self.step_bytes(BYTES_PER_FRAME);
+    }
+
+    /// Analyse about `budget` bytes, at least one block.
+    fn step_bytes(&mut self, budget: usize) {
+        let Some(bs) = &self.input else {
+            return;
+        };
+        if self.is_complete() {
+            return;
+        }
+        let Some(rest) = bs.seek(bs.start() + self.done * 8) else {
+            self.done = self.total;
+            return;
+        };
+        let mut bytes = rest.iter8().map(|(b, _)| b);
+        for _ in 0..(budget / self.block_size).max(1) {
+            let mut len = 0;
+            let block = analyze(bytes.by_ref().take(self.block_size).inspect(|_| len += 1));
+            if len == 0 {
+                self.done = self.total;
+                break;
+            }
+            self.blocks.push(block);
+            self.done += len;
+        }
+    }
+
+    /// Draw the strip, `view` is the bit range shown by the hex view. Return the clicked bit offset.
+    pub fn ui(&mut self, ui: &mut Ui, theme: &Theme, view: Range<usize>) -> Option<usize> {
+        let Some(bs) = &self.input else {
+            return None;
+        };
+        let start = bs.start();
+        let block_bits = self.block_size * 8;
+        if !self.is_complete() {
+            let percent = self.done * 100 / self.total.max(1);
+            ui.colored_label(theme.comment, format!("{}%", percent));
+        }
+        let mut clicked = None;
+        ScrollArea::vertical().auto_shrink([false, true]).show(ui, |ui| {
+            let width = ui.available_width();
+            let size = vec2(width, self.blocks.len() as f32 * BLOCK_HEIGHT);
+            let (rect, resp) = ui.allocate_exact_size(size, Sense::click());
+            let painter = ui.painter_at(rect);
+            let half = width / 2.0;
+            for (i, b) in self.blocks.iter().enumerate() {
+                let y = rect.top() + i as f32 * BLOCK_HEIGHT;
+                let class_rect =
+                    Rect::from_min_size(egui::pos2(rect.left(), y), vec2(half - 1.0, BLOCK_HEIGHT));
+                painter.rect_filled(class_rect, 0.0, b.class.color());
+                let level = (b.entropy / 8.0 * 255.0) as u8;
+                let entropy_rect =
+                    Rect::from_min_size(egui::pos2(rect.left() + half, y), vec2(half, BLOCK_HEIGHT));
+                painter.rect_filled(entropy_rect, 0.0, Color32::from_rgb(level, level / 2, 255 - level));
+            }
+            // frame the part of the input visible in the hex view
+            let to_y = |pos: usize| {
+                rect.top() + (pos.saturating_sub(start) / block_bits) as f32 * BLOCK_HEIGHT
+            };
+            let view_rect = Rect::from_x_y_ranges(
+                rect.x_range(),
+                to_y(view.start)..=to_y(view.end) + BLOCK_HEIGHT,
+            );
+            painter.rect_stroke(view_rect, 0.0, Stroke::new(1.0, theme.selection));
+            let block_at = |y: f32| ((y - rect.top()) / BLOCK_HEIGHT) as usize;
+            if let Some(pos) = resp.hover_pos() {
+                let i = block_at(pos.y);
+                if let Some(b) = self.blocks.get(i) {
+                    let offset = i * self.block_size;
+                    resp.clone().on_hover_text(format!(
+                        "{:06x}: {}, entropy {:.2}",
+                        offset,
+                        b.class.name(),
+                        b.entropy
+                    ));
+                }
+            }
+            if resp.clicked() {
+                if let Some(pos) = resp.interact_pointer_pos() {
+                    let i = block_at(pos.y).min(self.blocks.len().saturating_sub(1));
+                    clicked = Some(start + i * block_bits);
+                }
+            }
+        });
+        clicked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classes() {
+        assert_eq!(analyze([0; 64].into_iter()).class, ByteClass::Zeros);
+        assert_eq!(
+            analyze(b"hello, world\n".iter().copied()).class,
+            ByteClass::Text
+        );
+        let all: Vec<u8> = (0..=255).collect();
+        assert_eq!(analyze(all.into_iter()).class, ByteClass::Random);
+    }
+
+    #[test]
+    fn completes_when_a_block_exceeds_the_frame_budget() {
+        let mut overview = Overview::default();
+        overview.set_input(&Xbitstr::from(vec![0u8; 4096]));
+        assert_eq!(overview.block_size, 256);
+        for _ in 0..100 {
+            overview.step_bytes(100);
+        }
+        assert!(overview.is_complete());
+        assert_eq!(overview.blocks.len(), 16);
+    }
+}
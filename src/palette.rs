@@ -6,6 +6,12 @@ use fuzzy_matcher::skim::SkimMatcherV2;
 pub enum CommandAction {
     ToggleTheme,
     OpenBinary,
+    SaveBinary,
+    CompareBinary,
+    ExportResults,
+    ImportKaitai,
+    GenerateC,
+    GenerateRust,
     ToggleCanvas,
     ToggleBytecode,
     ToggleVariables,
@@ -21,6 +27,14 @@ pub enum CommandAction {
     ToggleWorkspaces,
     SaveWorkspace,
     ToggleFileExplorer,
+    ToggleFind,
+    ToggleInspector,
+    ToggleDisassembly,
+    ToggleHash,
+    ToggleStructure,
+    ToggleFormats,
+    ToggleBookmarks,
+    ToggleOverview,
 }
 
 #[derive(Clone)]
@@ -45,6 +59,12 @@ impl Default for Palette {
             commands: vec![
                 Command { name: "Theme: Toggle Editor".into(), action: CommandAction::ToggleTheme },
                 Command { name: "File: Open Binary...".into(), action: CommandAction::OpenBinary },
+                Command { name: "File: Save Binary As...".into(), action: CommandAction::SaveBinary },
+                Command { name: "File: Compare With...".into(), action: CommandAction::CompareBinary },
+                Command { name: "File: Export Results...".into(), action: CommandAction::ExportResults },
+                Command { name: "File: Import Kaitai Struct...".into(), action: CommandAction::ImportKaitai },
+                Command { name: "Code: Generate C Header".into(), action: CommandAction::GenerateC },
+                Command { name: "Code: Generate Rust Struct".into(), action: CommandAction::GenerateRust },
                 Command { name: "View: Toggle Canvas".into(), action: CommandAction::ToggleCanvas },
                 Command { name: "View: Toggle Bytecode".into(), action: CommandAction::ToggleBytecode },
                 Command { name: "View: Toggle Variables".into(), action: CommandAction::ToggleVariables },
@@ -60,6 +80,14 @@ impl Default for Palette {
                 Command { name: "Workspaces: Manage...".into(), action: CommandAction::ToggleWorkspaces },
                 Command { name: "Workspaces: Save".into(), action: CommandAction::SaveWorkspace },
                 Command { name: "View: Toggle File Explorer".into(), action: CommandAction::ToggleFileExplorer },
+                Command { name: "View: Find...".into(), action: CommandAction::ToggleFind },
+                Command { name: "View: Toggle Data Inspector".into(), action: CommandAction::ToggleInspector },
+                Command { name: "View: Toggle Disassembly".into(), action: CommandAction::ToggleDisassembly },
+                Command { name: "View: Toggle Hash".into(), action: CommandAction::ToggleHash },
+                Command { name: "View: Toggle Structure".into(), action: CommandAction::ToggleStructure },
+                Command { name: "View: Toggle Formats".into(), action: CommandAction::ToggleFormats },
+                Command { name: "View: Toggle Bookmarks".into(), action: CommandAction::ToggleBookmarks },
+                Command { name: "View: Toggle Overview".into(), action: CommandAction::ToggleOverview },
             ],
             selected_index: 0,
             matcher: SkimMatcherV2::default(),
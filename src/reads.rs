@@ -0,0 +1,164 @@
+use std::cell::RefCell;
+use std::ops::Range;
+use xeh::prelude::*;
+
+/// Bit range of the input consumed by a single `read`.
+#[derive(Clone)]
+pub struct Read {
+    pub range: Range<usize>,
+    pub val: Cell,
+    /// Input the range is relative to.
+    pub input: InputId,
+    /// Number of vars defined before the read, the index of the var that may hold it.
+    pub var_index: usize,
+}
+
+/// Cheap identity of an input: its bounds and leading bytes.
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct InputId {
+    start: usize,
+    end: usize,
+    head: u64,
+}
+
+impl InputId {
+    pub fn of(bs: &Xbitstr) -> Self {
+        let head = bs
+            .iter8()
+            .take(8)
+            .fold(0, |acc, (b, _)| acc << 8 | b as u64);
+        Self {
+            start: bs.start(),
+            end: bs.end(),
+            head,
+        }
+    }
+}
+
+/// Read matched with the variable that holds its value.
+pub struct VarRange {
+    pub range: Range<usize>,
+    pub name: Option<String>,
+    pub value: String,
+    pub color: egui::Color32,
+}
+
+thread_local! {
+    static READS: RefCell<(u64, Vec<Read>)> = const { RefCell::new((0, Vec::new())) };
+}
+
+fn update(f: impl FnOnce(&mut Vec<Read>)) {
+    READS.with(|r| {
+        let mut r = r.borrow_mut();
+        r.0 += 1;
+        f(&mut r.1);
+    });
+}
+
+fn record_read(xs: &mut Xstate) -> Xresult {
+    let end = xs.pop_data()?.to_usize()?;
+    let start = xs.pop_data()?.to_usize()?;
+    let val = xs.pop_data()?;
+    let input = InputId::of(xs.eval_named_value("input")?.bitstr()?);
+    let var_index = xs.var_list().len();
+    update(|r| {
+        r.push(Read {
+            range: start..end,
+            val: val.clone(),
+            input,
+            var_index,
+        })
+    });
+    xs.push_data(val)
+}
+
+/// Wrap `read` to log the consumed bit ranges, the body still refers to the builtin.
+/// Without tracking the scripts run on the builtin alone.
+pub fn install(xs: &mut Xstate, tracking: bool) -> Xresult {
+    clear();
+    if !tracking {
+        return Ok(());
+    }
+    xs.defword("playground/record-read", record_read)?;
+    xs.eval(": read offset swap read swap offset playground/record-read ;")
+}
+
+pub fn clear() {
+    update(|r| r.clear());
+}
+
+pub fn snapshot() -> Vec<Read> {
+    READS.with(|r| r.borrow().1.clone())
+}
+
+/// Counter changing on every update of the reads.
+pub fn generation() -> u64 {
+    READS.with(|r| r.borrow().0)
+}
+
+pub fn restore(reads: Vec<Read>) {
+    update(|r| *r = reads);
+}
+
+/// Distinct colour for the n-th range.
+pub fn range_color(n: usize) -> egui::Color32 {
+    let hue = (n as f32 * 0.618_034).fract();
+    egui::ecolor::Hsva::new(hue, 0.55, 0.95, 1.0).into()
+}
+
+/// Index of the read each var was defined from: the last read before the var,
+/// as long as the var still holds the value read.
+pub fn var_reads(reads: &[Read], vars: &[(Xstr, Cell)]) -> Vec<Option<usize>> {
+    let mut res = vec![None; vars.len()];
+    for (i, r) in reads.iter().enumerate() {
+        if let Some(slot) = res.get_mut(r.var_index) {
+            *slot = Some(i);
+        }
+    }
+    for (slot, (_, val)) in res.iter_mut().zip(vars) {
+        if slot.is_some_and(|i| &reads[i].val != val) {
+            *slot = None;
+        }
+    }
+    res
+}
+
+/// Ranges of the reads from the input, named after the user vars that hold them.
+pub fn assign(
+    reads: &[Read],
+    vars: &[(Xstr, Cell)],
+    boot_len: usize,
+    input: &Xbitstr,
+) -> Vec<VarRange> {
+    let mut names = vec![None; reads.len()];
+    for (k, i) in var_reads(reads, vars)
+        .into_iter()
+        .enumerate()
+        .skip(boot_len)
+    {
+        if let Some(i) = i {
+            names[i] = Some(vars[k].0.to_string());
+        }
+    }
+    let input = InputId::of(input);
+    let mut res: Vec<VarRange> = reads
+        .iter()
+        .zip(names)
+        .enumerate()
+        .filter(|(_, (r, _))| r.input == input && r.range.start < r.range.end)
+        .map(|(i, (r, name))| VarRange {
+            range: r.range.clone(),
+            name,
+            value: r.val.format_safe(),
+            color: range_color(i),
+        })
+        .collect();
+    res.sort_by_key(|r| r.range.start);
+    res
+}
+
+/// Ranges overlapping the bits, in the order of start.
+pub fn visible(ranges: &[VarRange], bits: Range<usize>) -> Vec<&VarRange> {
+    let n = ranges.partition_point(|r| r.range.start < bits.end);
+    ranges[..n].iter().filter(|r| r.range.end > bits.start).collect()
+}
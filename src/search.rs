@@ -0,0 +1,273 @@
+use crate::style::Theme;
+use egui::{Key, RichText, ScrollArea, TextEdit, Ui};
+
+const MAX_HITS: usize = 10_000;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SearchMode {
+    Hex,
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Bits,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Pattern {
+    /// Byte aligned pattern, `None` matches any byte.
+    Bytes(Vec<Option<u8>>),
+    /// Bit pattern matched at any bit offset, MSB first.
+    Bits(Vec<bool>),
+}
+
+impl Pattern {
+    pub fn len_bits(&self) -> usize {
+        match self {
+            Pattern::Bytes(v) => v.len() * 8,
+            Pattern::Bits(v) => v.len(),
+        }
+    }
+}
+
+pub fn parse_pattern(mode: SearchMode, text: &str) -> Result<Pattern, String> {
+    let pat = match mode {
+        SearchMode::Hex => {
+            let mut res = Vec::new();
+            for tok in text.split_whitespace() {
+                if let Some(c) = tok.chars().find(|c| !c.is_ascii_hexdigit() && *c != '?') {
+                    return Err(format!("bad hex digit {:?} in {:?}", c, tok));
+                }
+                if tok.len() % 2 != 0 {
+                    return Err(format!("odd number of hex digits in {:?}", tok));
+                }
+                for b in tok.as_bytes().chunks(2) {
+                    if b == b"??" {
+                        res.push(None);
+                    } else {
+                        let b = std::str::from_utf8(b).unwrap();
+                        let val = u8::from_str_radix(b, 16)
+                            .map_err(|_| format!("bad hex byte {:?}", b))?;
+                        res.push(Some(val));
+                    }
+                }
+            }
+            Pattern::Bytes(res)
+        }
+        SearchMode::Utf8 => Pattern::Bytes(text.bytes().map(Some).collect()),
+        SearchMode::Utf16Le => Pattern::Bytes(
+            text.encode_utf16()
+                .flat_map(|c| c.to_le_bytes())
+                .map(Some)
+                .collect(),
+        ),
+        SearchMode::Utf16Be => Pattern::Bytes(
+            text.encode_utf16()
+                .flat_map(|c| c.to_be_bytes())
+                .map(Some)
+                .collect(),
+        ),
+        SearchMode::Bits => {
+            let mut res = Vec::new();
+            for c in text.chars().filter(|c| !c.is_whitespace() && *c != '_') {
+                match c {
+                    '0' => res.push(false),
+                    '1' => res.push(true),
+                    _ => return Err(format!("bad bit {:?}, expecting 0 or 1", c)),
+                }
+            }
+            Pattern::Bits(res)
+        }
+    };
+    if pat.len_bits() == 0 {
+        return Err("empty pattern".to_string());
+    }
+    Ok(pat)
+}
+
+fn bit_at(bytes: &[u8], pos: usize) -> bool {
+    (bytes[pos / 8] >> (7 - pos % 8)) & 1 != 0
+}
+
+/// Offsets in bits of all occurrences of the pattern, overlapping included.
+pub fn find_all(bytes: &[u8], len_bits: usize, pat: &Pattern) -> Vec<usize> {
+    let mut hits = Vec::new();
+    match pat {
+        Pattern::Bytes(p) => {
+            let len = len_bits / 8;
+            if p.len() > len {
+                return hits;
+            }
+            for i in 0..=(len - p.len()) {
+                let eq = p
+                    .iter()
+                    .zip(&bytes[i..])
+                    .all(|(a, b)| a.map(|a| a == *b).unwrap_or(true));
+                if eq {
+                    hits.push(i * 8);
+                    if hits.len() >= MAX_HITS {
+                        break;
+                    }
+                }
+            }
+        }
+        Pattern::Bits(p) => {
+            if p.len() > len_bits {
+                return hits;
+            }
+            for i in 0..=(len_bits - p.len()) {
+                if p.iter().enumerate().all(|(j, b)| bit_at(bytes, i + j) == *b) {
+                    hits.push(i);
+                    if hits.len() >= MAX_HITS {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    hits
+}
+
+pub struct Search {
+    pub is_open: bool,
+    pub mode: SearchMode,
+    pub query: String,
+    pub hits: Vec<usize>,
+    pub current: Option<usize>,
+    pub match_bits: usize,
+    pub error: Option<String>,
+}
+
+impl Default for Search {
+    fn default() -> Self {
+        Self {
+            is_open: false,
+            mode: SearchMode::Hex,
+            query: String::new(),
+            hits: Vec::new(),
+            current: None,
+            match_bits: 0,
+            error: None,
+        }
+    }
+}
+
+impl Search {
+    /// Search the bytes, `start` is the bit offset of the first byte.
+    pub fn run(&mut self, bytes: &[u8], start: usize, len_bits: usize) {
+        self.hits.clear();
+        self.current = None;
+        match parse_pattern(self.mode, &self.query) {
+            Ok(pat) => {
+                self.error = None;
+                self.match_bits = pat.len_bits();
+                self.hits = find_all(bytes, len_bits, &pat)
+                    .into_iter()
+                    .map(|x| start + x)
+                    .collect();
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    /// Select the first hit after the position, wrap around at the end.
+    pub fn next(&mut self, pos: usize) -> Option<usize> {
+        let i = match self.current {
+            Some(i) if self.hits.get(i) == Some(&pos) => i + 1,
+            _ => self.hits.iter().position(|&x| x > pos).unwrap_or(0),
+        };
+        self.select(if i < self.hits.len() { i } else { 0 })
+    }
+
+    /// Select the last hit before the position, wrap around at the start.
+    pub fn prev(&mut self, pos: usize) -> Option<usize> {
+        let i = match self.current {
+            Some(i) if self.hits.get(i) == Some(&pos) => i.checked_sub(1),
+            _ => self.hits.iter().rposition(|&x| x < pos),
+        };
+        self.select(i.unwrap_or(self.hits.len().saturating_sub(1)))
+    }
+
+    fn select(&mut self, i: usize) -> Option<usize> {
+        let pos = *self.hits.get(i)?;
+        self.current = Some(i);
+        Some(pos)
+    }
+
+    /// Byte range of the selected hit.
+    pub fn current_bytes(&self) -> Option<std::ops::Range<usize>> {
+        let pos = *self.hits.get(self.current?)?;
+        Some(pos / 8..(pos + self.match_bits).div_ceil(8))
+    }
+
+    /// Returns true when search is requested, and the selected hit offset.
+    pub fn ui(
+        &mut self,
+        ui: &mut Ui,
+        theme: &Theme,
+        fmt_offset: impl Fn(usize) -> String,
+    ) -> (bool, Option<usize>) {
+        let mut run = false;
+        let mut jump = None;
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut self.mode, SearchMode::Hex, "Hex");
+            ui.selectable_value(&mut self.mode, SearchMode::Utf8, "UTF-8");
+            ui.selectable_value(&mut self.mode, SearchMode::Utf16Le, "UTF-16LE");
+            ui.selectable_value(&mut self.mode, SearchMode::Utf16Be, "UTF-16BE");
+            ui.selectable_value(&mut self.mode, SearchMode::Bits, "Bits");
+        });
+        let hint = match self.mode {
+            SearchMode::Hex => "50 4B ?? 04",
+            SearchMode::Bits => "1010 0001",
+            _ => "text",
+        };
+        ui.horizontal(|ui| {
+            ui.style_mut().visuals.extreme_bg_color = theme.code_background;
+            let resp = ui.add(TextEdit::singleline(&mut self.query).hint_text(hint));
+            if resp.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+                run = true;
+            }
+            if ui.button("Find").clicked() {
+                run = true;
+            }
+        });
+        if let Some(e) = &self.error {
+            ui.colored_label(theme.error, e);
+        } else if !self.hits.is_empty() {
+            let more = if self.hits.len() >= MAX_HITS { "+" } else { "" };
+            ui.colored_label(theme.comment, format!("{}{} hits", self.hits.len(), more));
+        }
+        ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+            for (i, &pos) in self.hits.iter().enumerate() {
+                let text = RichText::new(fmt_offset(pos)).monospace();
+                if ui.selectable_label(self.current == Some(i), text).clicked() {
+                    jump = Some(i);
+                }
+            }
+        });
+        let pos = jump.and_then(|i| self.select(i));
+        (run, pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_pattern() {
+        let pat = parse_pattern(SearchMode::Hex, "4d5a ??00").unwrap();
+        assert_eq!(
+            pat,
+            Pattern::Bytes(vec![Some(0x4d), Some(0x5a), None, Some(0)])
+        );
+        assert!(parse_pattern(SearchMode::Hex, "4d5").is_err());
+        assert!(parse_pattern(SearchMode::Hex, "?4").is_err());
+        assert!(parse_pattern(SearchMode::Hex, "+1").is_err());
+    }
+
+    #[test]
+    fn non_ascii_hex_is_an_error() {
+        assert!(parse_pattern(SearchMode::Hex, "aéb").is_err());
+        assert!(parse_pattern(SearchMode::Hex, "éé").is_err());
+    }
+}
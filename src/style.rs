@@ -31,6 +31,11 @@ pub struct Theme {
     pub background: Color32,
     pub selection: Color32,
     pub selection_background: Color32,
+    pub syntax_builtin: Color32,
+    pub syntax_word: Color32,
+    pub syntax_var: Color32,
+    pub syntax_number: Color32,
+    pub syntax_string: Color32,
     pub font_size: f32,
     current_item: usize,
 }
@@ -49,6 +54,11 @@ impl Default for Theme {
             error: Color32::from_rgb(0xff, 0x60, 0x60),
             debug_marker: Color32::from_rgb(0x99, 0x21, 0xaf),
             border: Color32::from_rgb(0x7a, 0xa4, 0x80),
+            syntax_builtin: Color32::from_rgb(0x35, 0xf0, 0x00),
+            syntax_word: Color32::from_rgb(0x5f, 0xc8, 0xf0),
+            syntax_var: Color32::from_rgb(0xf0, 0x9a, 0x50),
+            syntax_number: Color32::from_rgb(0xd0, 0x80, 0xf0),
+            syntax_string: Color32::from_rgb(0xd0, 0xc0, 0x00),
             font_size: 14.0,
             current_item: 0,
         }
@@ -69,6 +79,11 @@ impl Theme {
             ("Error", &mut self.error),
             ("Marker", &mut self.debug_marker),
             ("Border", &mut self.border),
+            ("Syntax Builtin Word", &mut self.syntax_builtin),
+            ("Syntax User Word", &mut self.syntax_word),
+            ("Syntax Variable", &mut self.syntax_var),
+            ("Syntax Number", &mut self.syntax_number),
+            ("Syntax String", &mut self.syntax_string),
         ];
         let mut reset = false;
         ui.horizontal_top(|ui| {
@@ -0,0 +1,238 @@
+use crate::reads::{self, InputId, Read};
+use crate::style::Theme;
+use egui::{Id, Label, RichText, ScrollArea, Sense, Ui};
+use std::ops::Range;
+use xeh::prelude::*;
+
+/// Children shown per node, the rest is summarized.
+const MAX_CHILDREN: usize = 500;
+const MAX_DEPTH: usize = 32;
+const MAX_VALUE_CHARS: usize = 60;
+
+pub struct Node {
+    pub label: String,
+    pub kind: &'static str,
+    pub value: String,
+    /// Bits of the input the value was read from.
+    pub range: Option<Range<usize>>,
+    pub children: Vec<Node>,
+}
+
+fn kind(cell: &Cell) -> &'static str {
+    if cell.is_nil() {
+        "nil"
+    } else if cell.vec().is_ok() {
+        "vector"
+    } else if cell.xmap().is_ok() {
+        "map"
+    } else if cell.bitstr().is_ok() {
+        "bitstr"
+    } else if cell.str().is_ok() {
+        "str"
+    } else if cell.to_xint().is_ok() {
+        "int"
+    } else if cell.to_real().is_ok() {
+        "real"
+    } else {
+        "other"
+    }
+}
+
+fn short(mut s: String) -> String {
+    if s.chars().count() > MAX_VALUE_CHARS {
+        s = s.chars().take(MAX_VALUE_CHARS - 3).collect();
+        s.push_str("...");
+    }
+    s
+}
+
+fn more(len: usize) -> Option<Node> {
+    (len > MAX_CHILDREN).then(|| Node {
+        label: "...".to_string(),
+        kind: "",
+        value: format!("{} more", len - MAX_CHILDREN),
+        range: None,
+        children: Vec::new(),
+    })
+}
+
+/// Ranges of container items and the stack, only bitstrs carry where they came from.
+struct Builder<'a> {
+    reads: &'a [Read],
+}
+
+impl Builder<'_> {
+    /// Range of a bitstr slice read from the input, a number could be any read of the same value.
+    fn find_read(&self, cell: &Cell) -> Option<Range<usize>> {
+        let bs = cell.bitstr().ok()?;
+        let range = bs.start()..bs.end();
+        let found = |r: &&Read| r.range == range && &r.val == cell;
+        self.reads.iter().find(found).map(|r| r.range.clone())
+    }
+
+    /// `read` is the range of a var defined from a read, `None` for the other values.
+    fn node(
+        &self,
+        label: String,
+        cell: &Cell,
+        depth: usize,
+        read: Option<Option<Range<usize>>>,
+    ) -> Node {
+        let kind = kind(cell);
+        let mut children = Vec::new();
+        if depth < MAX_DEPTH {
+            if let Ok(v) = cell.vec() {
+                for (i, c) in v.iter().take(MAX_CHILDREN).enumerate() {
+                    children.push(self.node(i.to_string(), c, depth + 1, None));
+                }
+                children.extend(more(v.len()));
+            } else if let Ok(m) = cell.xmap() {
+                for (k, c) in m.iter().take(MAX_CHILDREN) {
+                    children.push(self.node(k.format_safe(), c, depth + 1, None));
+                }
+                children.extend(more(m.len()));
+            }
+            if let Some(tags) = cell.tags() {
+                for (k, c) in tags.iter().take(MAX_CHILDREN) {
+                    children.push(self.node(format!("#{}", k.format_safe()), c, depth + 1, None));
+                }
+            }
+        }
+        let value = match (cell.vec(), cell.xmap(), cell.bitstr()) {
+            (Ok(v), _, _) => format!("[{} items]", v.len()),
+            (_, Ok(m), _) => format!("{{{} entries}}", m.len()),
+            (_, _, Ok(bs)) => format!("{} bits", bs.len()),
+            _ => short(cell.format_safe()),
+        };
+        // a bitstr has a range only if it was read, not if it was built by the script
+        let range = match read {
+            _ if cell.vec().is_ok() || cell.xmap().is_ok() => None,
+            Some(read) => read,
+            None => self.find_read(cell),
+        };
+        // containers cover the bits of their items
+        let range = range.or_else(|| {
+            let mut it = children.iter().filter_map(|c| c.range.clone());
+            let first = it.next()?;
+            Some(it.fold(first, |a, b| a.start.min(b.start)..a.end.max(b.end)))
+        });
+        Node {
+            label,
+            kind,
+            value,
+            range,
+            children,
+        }
+    }
+}
+
+/// Trees of the user variables and the stack, ranges are given for reads from the shown input.
+pub fn build(
+    vars: &[(Xstr, Cell)],
+    boot_len: usize,
+    stack: &[Cell],
+    all_reads: &[Read],
+    input: &Xbitstr,
+) -> Vec<Node> {
+    let input = InputId::of(input);
+    let var_reads = reads::var_reads(all_reads, vars);
+    let shown: Vec<Read> = all_reads
+        .iter()
+        .filter(|r| r.input == input)
+        .cloned()
+        .collect();
+    let b = Builder { reads: &shown };
+    let mut roots = Vec::new();
+    for (k, (name, val)) in vars.iter().enumerate().skip(boot_len) {
+        let read = var_reads[k]
+            .map(|i| &all_reads[i])
+            .filter(|r| r.input == input)
+            .map(|r| r.range.clone());
+        roots.push(b.node(name.to_string(), val, 0, Some(read)));
+    }
+    for (i, val) in stack.iter().rev().enumerate() {
+        roots.push(b.node(format!("stack {}", i), val, 0, None));
+    }
+    roots
+}
+
+#[derive(Default)]
+pub struct StructureTree {
+    pub is_open: bool,
+    pub roots: Vec<Node>,
+    /// State the tree was built for.
+    pub key: (u64, usize, usize, InputId),
+    selected: Option<Range<usize>>,
+}
+
+impl StructureTree {
+    /// Return the bit range of the clicked node.
+    pub fn ui(
+        &mut self,
+        ui: &mut Ui,
+        theme: &Theme,
+        fmt_offset: impl Fn(usize) -> String,
+    ) -> Option<Range<usize>> {
+        let mut clicked = None;
+        ScrollArea::vertical()
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                for (i, node) in self.roots.iter().enumerate() {
+                    let id = Id::new("structure").with(i);
+                    Self::ui_node(
+                        ui,
+                        theme,
+                        node,
+                        id,
+                        &self.selected,
+                        &fmt_offset,
+                        &mut clicked,
+                    );
+                }
+            });
+        if let Some(r) = &clicked {
+            self.selected = Some(r.clone());
+        }
+        clicked
+    }
+
+    fn ui_node(
+        ui: &mut Ui,
+        theme: &Theme,
+        node: &Node,
+        id: Id,
+        selected: &Option<Range<usize>>,
+        fmt_offset: &impl Fn(usize) -> String,
+        clicked: &mut Option<Range<usize>>,
+    ) {
+        let row = |ui: &mut Ui, clicked: &mut Option<Range<usize>>| {
+            let is_selected = node.range.is_some() && &node.range == selected;
+            let name = RichText::new(node.label.as_str()).color(if is_selected {
+                theme.selection
+            } else {
+                theme.text
+            });
+            let resp = ui.add(Label::new(name).sense(Sense::click()));
+            ui.colored_label(theme.comment, node.kind);
+            ui.colored_label(theme.code_frozen, node.value.as_str());
+            if let Some(r) = &node.range {
+                let text = format!("{}..{}", fmt_offset(r.start), fmt_offset(r.end));
+                ui.colored_label(theme.comment, text);
+                if resp.clicked() {
+                    *clicked = Some(r.clone());
+                }
+            }
+        };
+        if node.children.is_empty() {
+            ui.horizontal(|ui| row(ui, clicked));
+            return;
+        }
+        let state =
+            egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, false);
+        state.show_header(ui, |ui| row(ui, clicked)).body(|ui| {
+            for (i, c) in node.children.iter().enumerate() {
+                Self::ui_node(ui, theme, c, id.with(i), selected, fmt_offset, clicked);
+            }
+        });
+    }
+}
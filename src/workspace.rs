@@ -1,11 +1,40 @@
 use serde::{Deserialize, Serialize};
 use crate::agent::Task;
+use crate::bookmarks::BookmarkMap;
+use crate::inputs::SavedInput;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct Workspace {
+pub struct Buffer {
     pub name: String,
     pub code: String,
+}
+
+impl Buffer {
+    pub fn new(name: &str, code: String) -> Self {
+        Self {
+            name: name.to_string(),
+            code,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Workspace {
+    pub name: String,
+    // Single buffer workspaces saved before tabs were introduced
+    #[serde(default, skip_serializing)]
+    code: String,
+    #[serde(default)]
+    pub buffers: Vec<Buffer>,
+    #[serde(default)]
+    pub active_buffer: usize,
     pub tasks: Vec<Task>,
+    #[serde(default)]
+    pub bookmarks: BookmarkMap,
+    #[serde(default)]
+    pub inputs: Vec<SavedInput>,
+    #[serde(default)]
+    pub active_input: usize,
 }
 
 impl Default for Workspace {
@@ -13,7 +42,50 @@ impl Default for Workspace {
         Self {
             name: "Untitled".to_string(),
             code: String::new(),
+            buffers: vec![Buffer::new("main", String::new())],
+            active_buffer: 0,
             tasks: Vec::new(),
+            bookmarks: BookmarkMap::new(),
+            inputs: Vec::new(),
+            active_input: 0,
+        }
+    }
+}
+
+impl Workspace {
+    pub fn new(name: &str, buffers: Vec<Buffer>, active_buffer: usize, tasks: Vec<Task>) -> Self {
+        let mut ws = Self {
+            name: name.to_string(),
+            buffers,
+            active_buffer,
+            tasks,
+            ..Default::default()
+        };
+        ws.normalize();
+        ws
+    }
+
+    /// Upgrade old single buffer state and keep the active index in range.
+    pub fn normalize(&mut self) {
+        if self.buffers.is_empty() {
+            let code = std::mem::take(&mut self.code);
+            self.buffers.push(Buffer::new("main", code));
+        }
+        self.active_buffer = self.active_buffer.min(self.buffers.len() - 1);
+    }
+}
+
+/// Pick a buffer name not used by any of the `buffers`.
+pub fn unique_buffer_name(buffers: &[Buffer], base: &str) -> String {
+    if !buffers.iter().any(|b| b.name == base) {
+        return base.to_string();
+    }
+    let mut n = 2;
+    loop {
+        let name = format!("{}-{}", base, n);
+        if !buffers.iter().any(|b| b.name == name) {
+            return name;
         }
+        n += 1;
     }
 }